@@ -1,12 +1,14 @@
 #!/bin/bash
 # ContextKeeper Command Logger
 # Logs Bash commands executed by Claude Code for context recovery
+#
+# Uses `context-keeper history append` when the binary is available, which
+# redacts secrets, appends under a lock and rotates the log into compressed
+# archives. Falls back to a plain jq append otherwise.
 
 LOG_FILE="${CONTEXTKEEPER_LOG:-$HOME/.contextkeeper/command-history.jsonl}"
 LOG_DIR=$(dirname "$LOG_FILE")
-
-# Ensure log directory exists
-mkdir -p "$LOG_DIR"
+CONTEXTKEEPER_BIN="${CONTEXTKEEPER_BIN:-context-keeper}"
 
 # Read JSON input from stdin
 INPUT=$(cat)
@@ -14,26 +16,41 @@ INPUT=$(cat)
 # Extract fields
 COMMAND=$(echo "$INPUT" | jq -r '.tool_input.command // empty')
 CWD=$(echo "$INPUT" | jq -r '.cwd // empty')
-TIMESTAMP=$(date -u '+%Y-%m-%dT%H:%M:%SZ')
-SESSION_ID=$(echo "$INPUT" | jq -r '.session_id // empty')
 
 # Skip if no command
 if [ -z "$COMMAND" ]; then
     exit 0
 fi
 
+# Preferred path: Rust-side log store
+# (run from the command's cwd so project-level [history] settings apply)
+if command -v "$CONTEXTKEEPER_BIN" &> /dev/null; then
+    if printf '%s' "$INPUT" | (cd "${CWD:-.}" 2>/dev/null; "$CONTEXTKEEPER_BIN" history append) 2>/dev/null; then
+        exit 0
+    fi
+fi
+
+# Fallback: append with jq (no rotation, minimal redaction)
+mkdir -p "$LOG_DIR"
+
+TIMESTAMP=$(date -u '+%Y-%m-%dT%H:%M:%SZ')
+SESSION_ID=$(echo "$INPUT" | jq -r '.session_id // empty')
+# tool_response only has stdout/stderr/interrupted: the exit status is known
+# for interrupted commands alone, and there is no duration
+EXIT_CODE=$(echo "$INPUT" | jq -c 'if .tool_response.interrupted == true then 130 else null end')
+
+# Hide values of sensitive-looking variables, same rule as src/redact.rs:
+# upper-case names at the start of a word, so `max_tokens=4096` and
+# `-DUSE_TOKEN_CACHE=ON` are kept (no `I` flag: BSD sed lacks it)
+COMMAND=$(printf '%s' "$COMMAND" | sed -E 's/(^|[[:space:];&|(])([A-Z0-9_]*(TOKEN|SECRET|PASSWORD|PASSWD|API_?KEY|ACCESS_KEY|PRIVATE_KEY|CREDENTIALS?)[A-Z0-9_]*)=("[^"]*"|'"'"'[^'"'"']*'"'"'|[^[:space:]]+)/\1\2=[REDACTED]/g')
+
 # Write as JSONL (one JSON object per line)
 jq -n -c \
     --arg ts "$TIMESTAMP" \
     --arg cmd "$COMMAND" \
     --arg cwd "$CWD" \
     --arg session "$SESSION_ID" \
-    '{timestamp: $ts, command: $cmd, cwd: $cwd, session_id: $session}' >> "$LOG_FILE"
-
-# Rotate log if too large (>1MB)
-if [ -f "$LOG_FILE" ] && [ $(stat -f%z "$LOG_FILE" 2>/dev/null || stat -c%s "$LOG_FILE" 2>/dev/null) -gt 1048576 ]; then
-    tail -n 500 "$LOG_FILE" > "$LOG_FILE.tmp"
-    mv "$LOG_FILE.tmp" "$LOG_FILE"
-fi
+    --argjson exit_code "${EXIT_CODE:-null}" \
+    '{timestamp: $ts, command: $cmd, cwd: $cwd, session_id: $session, exit_code: $exit_code, duration_ms: null}' >> "$LOG_FILE"
 
 exit 0
@@ -0,0 +1,463 @@
+//! Build command synthesis - exact commands for a target, wrapped for its container
+
+use crate::collectors::default_runtime;
+use crate::config::Config;
+use crate::context::{BuildTarget, ContainerInfo, Context};
+use std::fs;
+use std::path::Path;
+
+/// Actions accepted by `get_build_command`
+pub const ACTIONS: &[&str] = &["build", "flash", "emulator", "shell"];
+
+/// A synthesized command and anything to do before running it
+#[derive(Debug, Default, Clone)]
+pub struct BuildCommand {
+    pub command: String,
+    pub notes: Vec<String>,
+}
+
+/// Build the command that performs `action` for `target_name`
+///
+/// The command runs inside the target's container when it has one. Errors are
+/// messages meant to be shown to the agent as-is.
+pub fn build_command(
+    config: &Config,
+    ctx: &Context,
+    target_name: &str,
+    action: &str,
+) -> Result<BuildCommand, String> {
+    if !ACTIONS.contains(&action) {
+        return Err(format!(
+            "Unknown action '{}'. Use one of: {}",
+            action,
+            ACTIONS.join(", ")
+        ));
+    }
+    let target = ctx
+        .targets
+        .iter()
+        .find(|t| t.name == target_name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = ctx.targets.iter().map(|t| t.name.as_str()).collect();
+            format!(
+                "Unknown target '{}'. Available targets: {}",
+                target_name,
+                names.join(", ")
+            )
+        })?;
+
+    let setup = setup_command(ctx, target);
+    let inner = match action {
+        "shell" => None,
+        _ => Some(action_command(config, ctx, target, action)?),
+    };
+
+    // Targets without a container of their own build in the project's running Dev Container
+    let devcontainer = ctx
+        .devcontainer
+        .as_ref()
+        .filter(|dc| !dc.container.is_empty());
+    let container_name = match devcontainer {
+        Some(dc) if target.container_name.is_empty() => dc.container.as_str(),
+        _ => target.container_name.as_str(),
+    };
+
+    let mut result = BuildCommand::default();
+    if container_name.is_empty() {
+        result.command = match (setup, inner) {
+            (Some(setup), Some(inner)) => format!("{} && {}", setup, inner),
+            (Some(setup), None) => setup,
+            (None, Some(inner)) => inner,
+            (None, None) => "bash".to_string(),
+        };
+        return Ok(result);
+    }
+
+    let container = ctx.containers.iter().find(|c| c.name == container_name);
+    let runtime = container
+        .map(|c| c.runtime.clone())
+        .unwrap_or_else(|| default_runtime(config));
+    match container {
+        Some(c) if c.running => {}
+        Some(c) => result.notes.push(format!(
+            "Container {} is not running ({}) — start it with `{} start {}` first",
+            c.name, c.status, runtime, c.name
+        )),
+        None => result.notes.push(format!(
+            "Container {} was not found — create it before running this command",
+            container_name
+        )),
+    }
+
+    let mut exec = format!("{} exec", runtime);
+    if inner.is_none() {
+        exec.push_str(" -it");
+    }
+    // Dev Containers expect commands to run as their remote user
+    let devcontainer = devcontainer.filter(|dc| dc.container == container_name);
+    if let Some(dc) = devcontainer.filter(|dc| !dc.remote_user.is_empty()) {
+        exec.push_str(&format!(" -u {}", dc.remote_user));
+    }
+
+    // Setup scripts like build/envsetup.sh are relative to the source tree, so
+    // start where the current directory is mounted rather than the image's default
+    let cwd = std::env::current_dir().and_then(fs::canonicalize).ok();
+    let workdir = container
+        .zip(cwd.as_deref())
+        .and_then(|(c, cwd)| container_workdir(c, cwd))
+        .or_else(|| devcontainer.map(|dc| dc.workspace_folder.clone()));
+    match (workdir, container) {
+        (Some(dir), _) => exec.push_str(&format!(" -w {}", dir)),
+        (None, Some(c)) => {
+            let start = if c.working_dir.is_empty() {
+                "the image's default directory"
+            } else {
+                &c.working_dir
+            };
+            result.notes.push(format!(
+                "No mount of container {} covers {} — the command starts in {}; \
+                 add `-w <dir>` if that is not the source tree",
+                c.name,
+                cwd.map(|d| d.display().to_string()).unwrap_or_default(),
+                start
+            ));
+        }
+        (None, None) => {}
+    }
+
+    // envsetup.sh and oe-init-build-env need bash; anything else runs under
+    // sh so images without bash work too
+    result.command = match (setup, inner) {
+        (Some(setup), Some(inner)) => format!(
+            "{} {} bash -lc {}",
+            exec,
+            container_name,
+            shell_quote(&format!("{} && {}", setup, inner))
+        ),
+        (Some(setup), None) => format!(
+            "{} {} bash -lc {}",
+            exec,
+            container_name,
+            shell_quote(&format!("{} && exec bash", setup))
+        ),
+        (None, Some(inner)) => format!(
+            "{} {} sh -c {}",
+            exec,
+            container_name,
+            shell_quote(&inner)
+        ),
+        (None, None) => format!("{} {} sh", exec, container_name),
+    };
+    Ok(result)
+}
+
+/// Path of `cwd` inside the container, through the mount that covers it most closely
+fn container_workdir(container: &ContainerInfo, cwd: &Path) -> Option<String> {
+    let (mount, rest) = container
+        .mounts
+        .iter()
+        .filter(|m| !m.source.is_empty() && !m.destination.is_empty())
+        .filter_map(|m| cwd.strip_prefix(&m.source).ok().map(|rest| (m, rest)))
+        .max_by_key(|(m, _)| m.source.len())?;
+
+    Some(if rest.as_os_str().is_empty() {
+        mount.destination.clone()
+    } else {
+        Path::new(&mount.destination).join(rest).display().to_string()
+    })
+}
+
+/// Environment setup a target needs before any command (AOSP lunch, Yocto build env)
+fn setup_command(ctx: &Context, target: &BuildTarget) -> Option<String> {
+    if !target.lunch_target.is_empty() {
+        return Some(format!(
+            "source build/envsetup.sh && lunch {}",
+            target.lunch_target
+        ));
+    }
+
+    if target.extra.get("kind").map(String::as_str) == Some("image") {
+        let yocto = ctx.yocto.as_ref()?;
+        // oe-init-build-env lives next to the core `meta` layer (poky/meta)
+        let script = yocto
+            .layers
+            .iter()
+            .filter_map(|layer| Path::new(layer).parent())
+            .map(|dir| dir.join("oe-init-build-env"))
+            .find(|script| script.is_file())
+            .map(|script| script.display().to_string())
+            .unwrap_or_else(|| "oe-init-build-env".to_string());
+        return Some(format!("source {} {}", script, yocto.build_dir));
+    }
+
+    None
+}
+
+/// The command for `action` once the environment is set up
+fn action_command(
+    config: &Config,
+    ctx: &Context,
+    target: &BuildTarget,
+    action: &str,
+) -> Result<String, String> {
+    // Explicit `build_command = "..."` etc. on a [[targets]] entry always wins
+    if let Some(command) = target.extra.get(&format!("{}_command", action)) {
+        return Ok(command.clone());
+    }
+
+    match action {
+        "flash" if !target.can_flash => {
+            return Err(format!("Target '{}' cannot be flashed", target.name))
+        }
+        "emulator" if !target.can_emulator => {
+            return Err(format!(
+                "Target '{}' does not support the emulator",
+                target.name
+            ))
+        }
+        _ => {}
+    }
+
+    let kind = target.extra.get("kind").map(String::as_str);
+    let package = target.extra.get("package");
+    let command = match (action, kind, package) {
+        ("build", _, _) if !target.lunch_target.is_empty() => Some("m".to_string()),
+        ("flash", _, _) if !target.lunch_target.is_empty() => {
+            Some("adb reboot bootloader && fastboot flashall".to_string())
+        }
+        ("emulator", _, _) if !target.lunch_target.is_empty() => Some("emulator".to_string()),
+        ("build", Some("bin"), Some(package)) => Some(format!(
+            "cargo build -p {} --bin {}",
+            package, target.name
+        )),
+        ("build", Some("test"), Some(package)) => Some(format!(
+            "cargo test --no-run -p {} --test {}",
+            package, target.name
+        )),
+        ("build", Some("image"), _) => Some(format!("bitbake {}", target.name)),
+        ("emulator", Some("image"), _) => ctx
+            .yocto
+            .as_ref()
+            .map(|y| format!("runqemu {} {} nographic", y.machine, target.name)),
+        _ => None,
+    };
+
+    command
+        .or_else(|| entry_point_command(config, ctx, target, action))
+        .ok_or_else(|| {
+            format!(
+                "No known {} command for target '{}'. Set `{}_command` in its [[targets]] entry.",
+                action, target.name, action
+            )
+        })
+}
+
+/// `<entry_point> <action> <target>` when the entry point script offers that subcommand
+fn entry_point_command(
+    config: &Config,
+    ctx: &Context,
+    target: &BuildTarget,
+    action: &str,
+) -> Option<String> {
+    let entry = config.scripts.as_ref()?.entry_point.as_ref()?;
+    let prefix = format!("{} {}", entry, action);
+
+    let line = ctx
+        .available_commands
+        .iter()
+        .map(|c| c.split("  #").next().unwrap_or(c).trim())
+        .find(|c| *c == prefix || c.starts_with(&format!("{} ", prefix)))?;
+
+    // Fill the first argument placeholder with the target name, drop the rest
+    let skip = prefix.split_whitespace().count();
+    let mut command = prefix;
+    let mut filled = false;
+    for arg in line.split_whitespace().skip(skip) {
+        if arg.starts_with('<') || arg.starts_with('[') {
+            if !filled {
+                command.push(' ');
+                command.push_str(&target.name);
+                filled = true;
+            }
+        } else {
+            command.push(' ');
+            command.push_str(arg);
+        }
+    }
+    Some(command)
+}
+
+/// Quote a string for a POSIX shell
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ContainerMount, DevContainer};
+
+    /// Bind mount of the directory the tests run in
+    fn source_mount(destination: &str) -> ContainerMount {
+        let cwd = fs::canonicalize(std::env::current_dir().unwrap()).unwrap();
+        ContainerMount {
+            kind: "bind".to_string(),
+            source: cwd.display().to_string(),
+            destination: destination.to_string(),
+            read_only: false,
+        }
+    }
+
+    fn aosp_context() -> Context {
+        Context {
+            targets: vec![
+                BuildTarget {
+                    name: "emu".to_string(),
+                    container_name: "aosp-build".to_string(),
+                    lunch_target: "sdk_phone64_x86_64-trunk_staging-userdebug".to_string(),
+                    can_emulator: true,
+                    ..Default::default()
+                },
+                BuildTarget {
+                    name: "shiba".to_string(),
+                    container_name: "aosp-build".to_string(),
+                    lunch_target: "aosp_shiba-userdebug".to_string(),
+                    can_flash: true,
+                    ..Default::default()
+                },
+            ],
+            containers: vec![ContainerInfo {
+                name: "aosp-build".to_string(),
+                runtime: "podman".to_string(),
+                running: true,
+                mounts: vec![source_mount("/src")],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aosp_build_in_container() {
+        let ctx = aosp_context();
+        let cmd = build_command(&Config::default(), &ctx, "emu", "build").unwrap();
+        assert_eq!(
+            cmd.command,
+            "podman exec -w /src aosp-build bash -lc 'source build/envsetup.sh && \
+             lunch sdk_phone64_x86_64-trunk_staging-userdebug && m'"
+        );
+        assert!(cmd.notes.is_empty());
+
+        let shell = build_command(&Config::default(), &ctx, "emu", "shell").unwrap();
+        assert_eq!(
+            shell.command,
+            "podman exec -it -w /src aosp-build bash -lc 'source build/envsetup.sh && \
+             lunch sdk_phone64_x86_64-trunk_staging-userdebug && exec bash'"
+        );
+
+        // A failed lunch must stop the flash
+        let flash = build_command(&Config::default(), &ctx, "shiba", "flash").unwrap();
+        assert_eq!(
+            flash.command,
+            "podman exec -w /src aosp-build bash -lc 'source build/envsetup.sh && \
+             lunch aosp_shiba-userdebug && adb reboot bootloader && fastboot flashall'"
+        );
+
+        assert!(build_command(&Config::default(), &ctx, "emu", "flash").is_err());
+        assert!(build_command(&Config::default(), &ctx, "missing", "build").is_err());
+    }
+
+    #[test]
+    fn test_workdir_from_mounts() {
+        let mut ctx = aosp_context();
+        ctx.containers[0].mounts = vec![ContainerMount {
+            source: "/somewhere/else".to_string(),
+            destination: "/src".to_string(),
+            ..Default::default()
+        }];
+        let cmd = build_command(&Config::default(), &ctx, "emu", "build").unwrap();
+        assert!(cmd.command.starts_with("podman exec aosp-build "));
+        assert!(cmd.notes[0].starts_with("No mount of container aosp-build covers"));
+
+        let container = ContainerInfo {
+            mounts: vec![
+                source_mount("/work"),
+                ContainerMount {
+                    source: "/".to_string(),
+                    destination: "/host".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let cwd = fs::canonicalize(std::env::current_dir().unwrap()).unwrap();
+        assert_eq!(
+            container_workdir(&container, &cwd.join("src")),
+            Some("/work/src".to_string())
+        );
+    }
+
+    #[test]
+    fn test_devcontainer_without_target_container() {
+        let mut ctx = aosp_context();
+        ctx.targets[0].container_name.clear();
+        ctx.containers[0].name = "vigilant_turing".to_string();
+        ctx.devcontainer = Some(DevContainer {
+            container: "vigilant_turing".to_string(),
+            remote_user: "vscode".to_string(),
+            workspace_folder: "/workspaces/aosp".to_string(),
+            ..Default::default()
+        });
+
+        let cmd = build_command(&Config::default(), &ctx, "emu", "shell").unwrap();
+        assert!(cmd
+            .command
+            .starts_with("podman exec -it -u vscode -w /src vigilant_turing bash -lc "));
+    }
+
+    #[test]
+    fn test_cargo_target_on_host() {
+        let mut target = BuildTarget {
+            name: "toolctl".to_string(),
+            ..Default::default()
+        };
+        target.extra.insert("kind".to_string(), "bin".to_string());
+        target.extra.insert("package".to_string(), "tool".to_string());
+        let ctx = Context {
+            targets: vec![target],
+            ..Default::default()
+        };
+
+        let cmd = build_command(&Config::default(), &ctx, "toolctl", "build").unwrap();
+        assert_eq!(cmd.command, "cargo build -p tool --bin toolctl");
+
+        // Building a test target compiles it without running it
+        let mut test = BuildTarget {
+            name: "integration".to_string(),
+            ..Default::default()
+        };
+        test.extra.insert("kind".to_string(), "test".to_string());
+        test.extra.insert("package".to_string(), "tool".to_string());
+        let ctx = Context {
+            targets: vec![test],
+            ..Default::default()
+        };
+        let cmd = build_command(&Config::default(), &ctx, "integration", "build").unwrap();
+        assert_eq!(cmd.command, "cargo test --no-run -p tool --test integration");
+
+        // Without envsetup.sh or oe-init-build-env there is no need for bash
+        let mut ctx = ctx;
+        ctx.targets[0].container_name = "rust-dev".to_string();
+        ctx.containers.push(ContainerInfo {
+            name: "rust-dev".to_string(),
+            runtime: "docker".to_string(),
+            running: true,
+            ..Default::default()
+        });
+        let cmd = build_command(&Config::default(), &ctx, "integration", "build").unwrap();
+        assert_eq!(
+            cmd.command,
+            "docker exec rust-dev sh -c 'cargo test --no-run -p tool --test integration'"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! History log maintenance commands (history append|prune)
+
+use std::io::{self, Read};
+
+use crate::config::read_config;
+use crate::history_log::HistoryLog;
+use crate::redact::Redactor;
+
+/// Run a `history` subcommand
+pub fn run_history_command(args: &[String]) -> io::Result<()> {
+    match args.first().map(|s| s.as_str()) {
+        Some("append") => append_from_hook(),
+        Some("prune") => prune(&args[1..]),
+        _ => {
+            eprintln!("Usage: context-keeper history <append|prune> [options]");
+            eprintln!("  append                 Log a command from PostToolUse hook JSON on stdin");
+            eprintln!("  prune [--keep N] [--days N]");
+            eprintln!("                         Rotate the log if due and delete old archives");
+            Ok(())
+        }
+    }
+}
+
+/// Append a command from PostToolUse hook JSON (redacted) to the history log
+fn append_from_hook() -> io::Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let hook: serde_json::Value = serde_json::from_str(&input).map_err(io::Error::other)?;
+
+    let config = read_config();
+    match hook_entry(&hook, &Redactor::from_config(&config)) {
+        Some(entry) => HistoryLog::from_config(&config).append(&entry),
+        None => Ok(()),
+    }
+}
+
+/// The log entry for a Bash PostToolUse payload, or None without a command
+///
+/// `tool_response` only carries `stdout`, `stderr` and `interrupted`, so the
+/// exit status is known just for interrupted commands (130) and the duration
+/// is never recorded here.
+fn hook_entry(hook: &serde_json::Value, redactor: &Redactor) -> Option<serde_json::Value> {
+    let command = hook["tool_input"]["command"].as_str().unwrap_or("");
+    if command.is_empty() {
+        return None;
+    }
+
+    let interrupted = hook["tool_response"]["interrupted"].as_bool() == Some(true);
+    Some(serde_json::json!({
+        "timestamp": chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        "command": redactor.redact(command),
+        "cwd": hook["cwd"].as_str().unwrap_or(""),
+        "session_id": hook["session_id"].as_str().unwrap_or(""),
+        "exit_code": interrupted.then_some(130),
+        "duration_ms": null,
+    }))
+}
+
+/// Rotate and prune archives, printing a short report
+fn prune(args: &[String]) -> io::Result<()> {
+    // Missing, non-numeric or out-of-range values are refused rather than
+    // ignored or clamped, since a bad retention deletes every archive
+    let flag_value = |name: &str, min: i64| -> Result<Option<i64>, String> {
+        let value = match args.iter().position(|a| a == name) {
+            Some(i) => args.get(i + 1),
+            None => return Ok(None),
+        };
+        match value.and_then(|v| v.parse::<i64>().ok()) {
+            Some(n) if n >= min => Ok(Some(n)),
+            _ => Err(format!("{} needs a whole number of at least {}", name, min)),
+        }
+    };
+    let (keep, days) = match (flag_value("--keep", 0), flag_value("--days", 1)) {
+        (Ok(keep), Ok(days)) => (keep, days),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
+
+    let log = HistoryLog::from_config(&read_config())
+        .with_retention(keep.map(|n| n as usize), days);
+    let report = match log.prune() {
+        Ok(report) => report,
+        Err(e) if e.kind() == io::ErrorKind::InvalidInput => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    println!("History log: {}", log.path().display());
+    if let Some(archive) = &report.rotated {
+        println!("  Rotated into {}", archive.display());
+    }
+    for removed in &report.removed {
+        println!("  Removed {}", removed.display());
+    }
+    println!("  {} archive(s) kept", report.remaining);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    /// A Bash PostToolUse payload as Claude Code sends it
+    const POST_TOOL_USE: &str = r#"{
+        "session_id": "abc123",
+        "transcript_path": "/home/dev/.claude/projects/aosp/abc123.jsonl",
+        "cwd": "/work/aosp",
+        "permission_mode": "default",
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "API_KEY=s3cr3t m -j32", "description": "Build"},
+        "tool_response": {"stdout": "build completed", "stderr": "", "interrupted": false, "isImage": false}
+    }"#;
+
+    #[test]
+    fn test_hook_entry_from_post_tool_use() {
+        let redactor = Redactor::from_config(&Config::default());
+        let mut hook: serde_json::Value = serde_json::from_str(POST_TOOL_USE).unwrap();
+
+        let entry = hook_entry(&hook, &redactor).unwrap();
+        assert_eq!(entry["command"], "API_KEY=[REDACTED] m -j32");
+        assert_eq!(entry["cwd"], "/work/aosp");
+        assert_eq!(entry["session_id"], "abc123");
+        assert!(entry["exit_code"].is_null());
+        assert!(entry["duration_ms"].is_null());
+
+        hook["tool_response"]["interrupted"] = true.into();
+        assert_eq!(hook_entry(&hook, &redactor).unwrap()["exit_code"], 130);
+
+        hook["tool_input"]["command"] = "".into();
+        assert!(hook_entry(&hook, &redactor).is_none());
+    }
+}
@@ -40,6 +40,11 @@ fn detect_project_type() -> Option<&'static str> {
         }
     }
 
+    // Check for Rust/Cargo
+    if Path::new("Cargo.toml").exists() {
+        return Some("cargo");
+    }
+
     None
 }
 
@@ -138,6 +143,11 @@ fn get_default_history_patterns(project_type: &str) -> Vec<&'static str> {
             r"MACHINE=",
             r"devtool\s+\S+",
         ],
+        "cargo" => vec![
+            r"cargo\s+(build|test|run)",
+            r"rustup\s+(default|override)",
+            r"export\s+\w+=",
+        ],
         _ => vec![r"export\s+\w+=", r"source\s+"],
     }
 }
@@ -162,7 +172,7 @@ pub fn run_init_wizard() -> io::Result<()> {
     let detected_type = detect_project_type();
     let type_hint = detected_type
         .map(|t| format!("detected: {}", t))
-        .unwrap_or_else(|| "aosp/ros/yocto/custom".to_string());
+        .unwrap_or_else(|| "aosp/ros/yocto/cargo/custom".to_string());
     let project_type = prompt(
         &format!("Project type ({})", type_hint),
         detected_type.unwrap_or("custom"),
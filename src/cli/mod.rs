@@ -2,6 +2,12 @@
 
 mod init;
 mod context;
+mod history;
+mod redact;
+mod target;
 
 pub use init::run_init_wizard;
 pub use context::run_context_command;
+pub use history::run_history_command;
+pub use redact::run_redact_command;
+pub use target::run_target_command;
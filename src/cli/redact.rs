@@ -0,0 +1,15 @@
+//! Secret redaction filter (--redact)
+
+use std::io::{self, Read, Write};
+
+use crate::config::read_config;
+use crate::redact::Redactor;
+
+/// Read stdin, redact secrets and write the result to stdout
+pub fn run_redact_command() -> io::Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+
+    let redactor = Redactor::from_config(&read_config());
+    io::stdout().write_all(redactor.redact(&input).as_bytes())
+}
@@ -0,0 +1,54 @@
+//! Active target selection command (target [set <name>|clear])
+
+use std::io;
+
+use crate::collectors::{clear_active_target, collect_targets, save_active_target};
+use crate::config::read_config;
+use crate::formatters::format_active_target;
+
+/// Run a `target` subcommand
+pub fn run_target_command(args: &[String]) -> io::Result<()> {
+    match args.first().map(|s| s.as_str()) {
+        None => {
+            let context = collect_targets(&read_config());
+            match (&context.active_target, format_active_target(&context)) {
+                (Some(_), Some(active)) => println!("Active target: {}", active),
+                _ => println!("No active target"),
+            }
+        }
+        Some("set") => match args.get(1) {
+            Some(name) => set(name)?,
+            None => eprintln!("Usage: context-keeper target set <name>"),
+        },
+        Some("clear") => {
+            if clear_active_target()? {
+                println!("Active target cleared");
+            } else {
+                println!("No active target was set");
+            }
+        }
+        _ => {
+            eprintln!("Usage: context-keeper target [set <name> | clear]");
+            eprintln!("  (no args)              Show the active target");
+            eprintln!("  set <name>             Select the target to work on for this project");
+            eprintln!("  clear                  Forget the selection");
+        }
+    }
+    Ok(())
+}
+
+/// Select a target by name, refusing names that are not discovered
+fn set(name: &str) -> io::Result<()> {
+    let context = collect_targets(&read_config());
+    match context.targets.iter().find(|t| t.name == name) {
+        Some(target) => {
+            save_active_target(target)?;
+            println!("Active target: {}", name);
+        }
+        None => {
+            let names: Vec<&str> = context.targets.iter().map(|t| t.name.as_str()).collect();
+            eprintln!("Unknown target '{}'. Available targets: {}", name, names.join(", "));
+        }
+    }
+    Ok(())
+}
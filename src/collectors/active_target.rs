@@ -0,0 +1,153 @@
+//! Active target persistence - the target selected with set_active_target, per project
+
+use crate::context::{ActiveTarget, BuildTarget};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Get the path to the active target file (shared by all projects)
+fn get_active_targets_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(format!("{}/.contextkeeper/active-targets.json", home))
+}
+
+/// Selections are keyed by the project directory
+fn project_key() -> String {
+    std::env::current_dir()
+        .and_then(fs::canonicalize)
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_else(|_| ".".to_string())
+}
+
+/// Take an exclusive lock shared by every session updating the file
+fn lock(path: &Path) -> io::Result<fs::File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut lock_path = path.to_path_buf().into_os_string();
+    lock_path.push(".lock");
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Read every project's selection; a file that does not parse is an error so
+/// that saving never overwrites the other projects' selections
+fn load_all(path: &Path) -> io::Result<BTreeMap<String, ActiveTarget>> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not valid: {}", path.display(), e),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Replace the file atomically, so a crash mid-write cannot lose every project's selection
+fn save_all(path: &Path, targets: &BTreeMap<String, ActiveTarget>) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(targets).map_err(io::Error::other)?;
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+fn load_in(path: &Path, key: &str) -> Option<ActiveTarget> {
+    load_all(path).ok()?.remove(key)
+}
+
+fn save_in(path: &Path, key: &str, target: &BuildTarget) -> io::Result<ActiveTarget> {
+    let active = ActiveTarget {
+        name: target.name.clone(),
+        container_name: target.container_name.clone(),
+        lunch_target: target.lunch_target.clone(),
+        set_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let _lock = lock(path)?;
+    let mut all = load_all(path)?;
+    all.insert(key.to_string(), active.clone());
+    save_all(path, &all)?;
+    Ok(active)
+}
+
+fn clear_in(path: &Path, key: &str) -> io::Result<bool> {
+    let _lock = lock(path)?;
+    let mut all = load_all(path)?;
+    if all.remove(key).is_none() {
+        return Ok(false);
+    }
+    save_all(path, &all)?;
+    Ok(true)
+}
+
+/// Load the active target selected for the current project
+pub fn load_active_target() -> Option<ActiveTarget> {
+    load_in(&get_active_targets_path(), &project_key())
+}
+
+/// Select `target` as the active target of the current project
+pub fn save_active_target(target: &BuildTarget) -> io::Result<ActiveTarget> {
+    save_in(&get_active_targets_path(), &project_key(), target)
+}
+
+/// Forget the current project's selection; returns false if none was set
+pub fn clear_active_target() -> io::Result<bool> {
+    clear_in(&get_active_targets_path(), &project_key())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load_clear_per_project() {
+        let dir = std::env::temp_dir().join(format!("ck-active-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("active-targets.json");
+        let target = BuildTarget {
+            name: "emu".to_string(),
+            container_name: "aosp-build".to_string(),
+            lunch_target: "sdk_phone64_x86_64-userdebug".to_string(),
+            ..Default::default()
+        };
+
+        assert!(load_in(&path, "/src/aosp").is_none());
+        save_in(&path, "/src/aosp", &target).unwrap();
+        save_in(
+            &path,
+            "/src/robot",
+            &BuildTarget {
+                name: "nav".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let loaded = load_in(&path, "/src/aosp").unwrap();
+        assert_eq!(loaded.name, "emu");
+        assert_eq!(loaded.container_name, "aosp-build");
+        assert_eq!(loaded.lunch_target, "sdk_phone64_x86_64-userdebug");
+
+        assert!(clear_in(&path, "/src/aosp").unwrap());
+        assert!(!clear_in(&path, "/src/aosp").unwrap());
+        assert!(load_in(&path, "/src/aosp").is_none());
+        assert_eq!(load_in(&path, "/src/robot").unwrap().name, "nav");
+        assert!(!dir.join("active-targets.json.tmp").exists());
+
+        // A corrupt file is left alone instead of being replaced by one project's entry
+        fs::write(&path, "{ not json").unwrap();
+        assert!(save_in(&path, "/src/aosp", &target).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,633 @@
+//! Assignment lexer - reads variables from shell-style config files
+//!
+//! Understands the subset of shell syntax that target config files use
+//! (`export`/`readonly`, quoting, escapes, comments, line continuations and
+//! `${VAR:-default}` expansion) plus the `?=`/`+=`/`:=` style operators found
+//! in BitBake `.conf` files and makefile fragments.
+
+/// Builtins whose arguments are assignments (`export FOO=bar`)
+const DECLARATION_BUILTINS: &[&str] = &["export", "readonly", "declare", "typeset", "local"];
+
+/// Parse every variable assignment in `content`
+///
+/// Returns final values in order of first assignment. References to variables
+/// assigned earlier in the same file are expanded; unknown references are kept
+/// as written so the output stays readable.
+pub fn parse_assignments(content: &str) -> Vec<(String, String)> {
+    lex(content).vars
+}
+
+/// Parse a BitBake `.conf` file, also reporting which variables were only
+/// given a default (`?=`/`??=`), which a value from the environment overrides
+pub fn parse_conf_assignments(content: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let lexer = lex(content);
+    (lexer.vars, lexer.defaults)
+}
+
+fn lex(content: &str) -> Lexer {
+    let mut lexer = Lexer {
+        chars: content.chars().collect(),
+        pos: 0,
+        vars: Vec::new(),
+        defaults: Vec::new(),
+    };
+    lexer.run();
+    lexer
+}
+
+/// How a value combines with the previous one
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AssignOp {
+    Set,
+    /// `?=` / `??=` - only if not yet assigned
+    Default,
+    /// `+=` (space-separated in conf files, concatenated in shell)
+    Append,
+    /// `=+`
+    Prepend,
+    /// `.=`
+    AppendRaw,
+    /// `=.`
+    PrependRaw,
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    vars: Vec<(String, String)>,
+    /// Variables whose value so far comes from `?=`/`??=` alone
+    defaults: Vec<String>,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn lookup(&self, name: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn run(&mut self) {
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None => break,
+                Some('\n' | ';' | '&' | '|') => self.pos += 1,
+                Some('#') => self.skip_line(),
+                Some(_) => self.command(),
+            }
+        }
+    }
+
+    /// Skip spaces, tabs and line continuations; returns true if anything was skipped
+    fn skip_blanks(&mut self) -> bool {
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => self.pos += 1,
+                Some('\\') if self.peek_at(1) == Some('\n') => self.pos += 2,
+                _ => break,
+            }
+        }
+        self.pos > start
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn at_command_end(&self) -> bool {
+        matches!(self.peek(), None | Some('\n' | ';' | '&' | '|'))
+    }
+
+    /// Skip the remaining words of a command that is not an assignment
+    fn skip_command(&mut self) {
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                _ if self.at_command_end() => break,
+                Some('#') => self.skip_line(),
+                Some('(' | ')') => self.pos += 1,
+                _ => {
+                    self.word(false);
+                }
+            }
+        }
+    }
+
+    fn read_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            let valid =
+                c == '_' || c.is_ascii_alphabetic() || (!name.is_empty() && c.is_ascii_digit());
+            if !valid {
+                break;
+            }
+            name.push(c);
+            self.pos += 1;
+        }
+        name
+    }
+
+    fn command(&mut self) {
+        let start = self.pos;
+        let name = self.read_name();
+
+        if DECLARATION_BUILTINS.contains(&name.as_str()) && self.skip_blanks() {
+            self.declaration();
+        } else if name.is_empty() || !self.assignment(name) {
+            self.pos = start;
+            self.skip_command();
+        }
+    }
+
+    /// Arguments of `export`/`readonly`: options, bare names and assignments
+    fn declaration(&mut self) {
+        loop {
+            self.skip_blanks();
+            if self.at_command_end() || self.peek() == Some('#') {
+                break;
+            }
+
+            let start = self.pos;
+            let name = self.read_name();
+            let is_assignment = !name.is_empty()
+                && (self.peek() == Some('=')
+                    || (self.peek() == Some('+') && self.peek_at(1) == Some('=')));
+
+            if is_assignment {
+                self.assignment(name);
+            } else {
+                self.pos = start;
+                self.word(false);
+                if self.pos == start {
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Parse the operator and value after `name`; returns false if this is not an assignment
+    fn assignment(&mut self, name: String) -> bool {
+        let spaced = self.skip_blanks();
+        let op = match self.operator(spaced) {
+            Some(op) => op,
+            None => return false,
+        };
+
+        // `FOO=bar` is a shell word; `FOO = "bar"`, `FOO ?= ...` and `FOO := a b`
+        // take the rest of the line like BitBake and make do
+        let conf = spaced
+            || !matches!(op, AssignOp::Set | AssignOp::Append)
+            || matches!(self.peek(), Some(' ' | '\t'));
+
+        let value = if conf {
+            let mut words = Vec::new();
+            loop {
+                self.skip_blanks();
+                if matches!(self.peek(), None | Some('\n' | '#')) {
+                    break;
+                }
+                words.push(self.word(true));
+            }
+            words.join(" ")
+        } else {
+            self.word(false)
+        };
+
+        self.assign(name, op, value, conf);
+        true
+    }
+
+    fn operator(&mut self, spaced: bool) -> Option<AssignOp> {
+        let ops: &[(&str, AssignOp)] = &[
+            ("??=", AssignOp::Default),
+            ("?=", AssignOp::Default),
+            (":=", AssignOp::Set),
+            ("+=", AssignOp::Append),
+            (".=", AssignOp::AppendRaw),
+            ("=+", AssignOp::Prepend),
+            ("=.", AssignOp::PrependRaw),
+            ("=", AssignOp::Set),
+        ];
+
+        for (text, op) in ops {
+            // `FOO=+x` in shell is a plain assignment of "+x"
+            if !spaced && matches!(op, AssignOp::Prepend | AssignOp::PrependRaw) {
+                continue;
+            }
+            let matches = text
+                .chars()
+                .enumerate()
+                .all(|(i, c)| self.peek_at(i) == Some(c));
+            if matches {
+                self.pos += text.len();
+                return Some(*op);
+            }
+        }
+        None
+    }
+
+    fn assign(&mut self, name: String, op: AssignOp, value: String, conf: bool) {
+        let current = self.lookup(&name).map(str::to_string);
+        let sep = if conf { " " } else { "" };
+
+        match (op, &current) {
+            (AssignOp::Default, None) => self.defaults.push(name.clone()),
+            (AssignOp::Set, _) => self.defaults.retain(|n| *n != name),
+            _ => {}
+        }
+
+        let value = match (op, current) {
+            (AssignOp::Default, Some(_)) => return,
+            (AssignOp::Set | AssignOp::Default, _) | (_, None) => value,
+            (AssignOp::Append, Some(cur)) => format!("{}{}{}", cur, sep, value),
+            (AssignOp::Prepend, Some(cur)) => format!("{} {}", value, cur),
+            (AssignOp::AppendRaw, Some(cur)) => format!("{}{}", cur, value),
+            (AssignOp::PrependRaw, Some(cur)) => format!("{}{}", value, cur),
+        };
+
+        match self.vars.iter_mut().find(|(n, _)| *n == name) {
+            Some(slot) => slot.1 = value,
+            None => self.vars.push((name, value)),
+        }
+    }
+
+    /// Lex one word, removing quotes and expanding references
+    ///
+    /// In `conf` mode only whitespace ends a word; otherwise shell operators do too.
+    fn word(&mut self, conf: bool) -> String {
+        let mut out = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' => break,
+                ';' | '&' | '|' | '(' | ')' if !conf => break,
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(next) => {
+                            out.push(next);
+                            self.pos += 1;
+                        }
+                        None => out.push('\\'),
+                    }
+                }
+                '\'' => {
+                    self.pos += 1;
+                    while let Some(c) = self.peek() {
+                        self.pos += 1;
+                        if c == '\'' {
+                            break;
+                        }
+                        out.push(c);
+                    }
+                }
+                '"' => {
+                    self.pos += 1;
+                    self.double_quoted(&mut out);
+                }
+                '$' => self.expand(&mut out),
+                _ => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        out
+    }
+
+    fn double_quoted(&mut self, out: &mut String) {
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    break;
+                }
+                '\\' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\n') => self.pos += 1,
+                        Some(next @ ('"' | '\\' | '$' | '`')) => {
+                            out.push(next);
+                            self.pos += 1;
+                        }
+                        _ => out.push('\\'),
+                    }
+                }
+                '$' => self.expand(out),
+                _ => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Expand `$VAR`, `${VAR}`, `${VAR:-default}` or a make-style `$(VAR)` at the cursor
+    fn expand(&mut self, out: &mut String) {
+        let start = self.pos;
+        self.pos += 1;
+
+        match self.peek() {
+            Some('{') => {
+                let body = self.balanced('{', '}');
+                let text: String = self.chars[start..self.pos].iter().collect();
+                out.push_str(&self.expand_braced(&body).unwrap_or(text));
+            }
+            Some('(') => {
+                let body = self.balanced('(', ')');
+                match self.lookup(&body) {
+                    Some(value) => out.push_str(value),
+                    None => out.extend(&self.chars[start..self.pos]),
+                }
+            }
+            _ => {
+                let name = self.read_name();
+                match self.lookup(&name) {
+                    Some(value) if !name.is_empty() => out.push_str(value),
+                    _ => out.extend(&self.chars[start..self.pos]),
+                }
+            }
+        }
+    }
+
+    /// Consume a bracketed group at the cursor and return its inner text
+    fn balanced(&mut self, open: char, close: char) -> String {
+        let mut depth = 0;
+        let mut body = String::new();
+
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == open {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            body.push(c);
+        }
+        body
+    }
+
+    /// Resolve the body of `${...}`; None leaves the reference unexpanded
+    fn expand_braced(&self, body: &str) -> Option<String> {
+        let name_len = body
+            .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+            .unwrap_or(body.len());
+        let (name, rest) = body.split_at(name_len);
+        let value = self.lookup(name);
+
+        let (op, word) = [":-", ":=", ":+", "-", "=", "+"]
+            .iter()
+            .find_map(|op| rest.strip_prefix(op).map(|word| (*op, word)))
+            .unwrap_or(("", rest));
+        if !rest.is_empty() && op.is_empty() {
+            return None;
+        }
+
+        let unset_or_empty = value.is_none_or(str::is_empty);
+        let word = || {
+            let mut nested = Lexer {
+                chars: word.chars().collect(),
+                pos: 0,
+                vars: self.vars.clone(),
+                defaults: Vec::new(),
+            };
+            nested.word(true)
+        };
+
+        match op {
+            "" => value.map(str::to_string),
+            ":-" | ":=" if unset_or_empty => Some(word()),
+            "-" | "=" if value.is_none() => Some(word()),
+            ":+" if !unset_or_empty => Some(word()),
+            "+" if value.is_some() => Some(word()),
+            ":+" | "+" => Some(String::new()),
+            _ => value.map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get<'a>(vars: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        vars.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_shell_quoting_and_comments() {
+        let vars = parse_assignments(
+            r#"
+# Build target for Pixel
+export TARGET_NAME="pixel 8"  # display name
+readonly LUNCH_TARGET=aosp_shiba-trunk_staging-userdebug
+DESC='It'\''s "quoted"'
+ESCAPED="say \"hi\" \$HOME"
+echo NOT_ME=1; AFTER=x
+A=1 B=2 make -j8 C=3
+"#,
+        );
+
+        assert_eq!(get(&vars, "TARGET_NAME"), Some("pixel 8"));
+        assert_eq!(
+            get(&vars, "LUNCH_TARGET"),
+            Some("aosp_shiba-trunk_staging-userdebug")
+        );
+        assert_eq!(get(&vars, "DESC"), Some(r#"It's "quoted""#));
+        assert_eq!(get(&vars, "ESCAPED"), Some(r#"say "hi" $HOME"#));
+        assert_eq!(get(&vars, "NOT_ME"), None);
+        assert_eq!(get(&vars, "AFTER"), Some("x"));
+        assert_eq!(get(&vars, "B"), Some("2"));
+        assert_eq!(get(&vars, "C"), None);
+    }
+
+    #[test]
+    fn test_aosp_target_conf() {
+        let vars = parse_assignments(
+            r#"
+#!/bin/bash
+# Cuttlefish x86_64 phone, built inside the aosp-build container
+export TARGET_NAME=cf_x86_64_phone
+export TARGET_DESCRIPTION="Cuttlefish virtual device (x86_64)"
+CONTAINER_NAME=${AOSP_CONTAINER:-aosp-build}
+LUNCH_TARGET="aosp_cf_x86_64_phone-trunk_staging-userdebug"
+OUT_ROOT=/work/aosp/out
+PRODUCT_OUT="${OUT_ROOT}/target/product/vsoc_x86_64"
+KERNEL_DIR=${KERNEL_DIR:-$OUT_ROOT/../kernel}
+BUILD_FLAGS="-j$(nproc) \
+    showcommands"
+CAN_EMULATOR=true
+CAN_FLASH=false
+"#,
+        );
+
+        assert_eq!(get(&vars, "TARGET_NAME"), Some("cf_x86_64_phone"));
+        assert_eq!(
+            get(&vars, "TARGET_DESCRIPTION"),
+            Some("Cuttlefish virtual device (x86_64)")
+        );
+        assert_eq!(get(&vars, "CONTAINER_NAME"), Some("aosp-build"));
+        assert_eq!(
+            get(&vars, "PRODUCT_OUT"),
+            Some("/work/aosp/out/target/product/vsoc_x86_64")
+        );
+        assert_eq!(get(&vars, "KERNEL_DIR"), Some("/work/aosp/out/../kernel"));
+        assert_eq!(
+            get(&vars, "BUILD_FLAGS"),
+            Some("-j$(nproc)     showcommands")
+        );
+        assert_eq!(get(&vars, "CAN_EMULATOR"), Some("true"));
+    }
+
+    #[test]
+    fn test_aosp_board_config_makefile() {
+        let vars = parse_assignments(
+            r#"
+TARGET_ARCH := arm64
+TARGET_ARCH_VARIANT := armv8-2a
+TARGET_CPU_VARIANT := generic
+TARGET_BOARD_PLATFORM := zuma
+BOARD_KERNEL_CMDLINE := console=ttyS0
+BOARD_KERNEL_CMDLINE += androidboot.hardware=$(TARGET_BOARD_PLATFORM)
+PRODUCT_OUT = out/target/product/$(TARGET_BOARD_PLATFORM)
+"#,
+        );
+
+        assert_eq!(get(&vars, "TARGET_ARCH"), Some("arm64"));
+        assert_eq!(
+            get(&vars, "BOARD_KERNEL_CMDLINE"),
+            Some("console=ttyS0 androidboot.hardware=zuma")
+        );
+        assert_eq!(get(&vars, "PRODUCT_OUT"), Some("out/target/product/zuma"));
+    }
+
+    #[test]
+    fn test_yocto_local_conf() {
+        let vars = parse_assignments(
+            r#"
+# This sets the default machine to be qemux86-64 if no other machine is selected:
+MACHINE ??= "qemux86-64"
+MACHINE = "raspberrypi4-64"
+DISTRO ?= "poky"
+DISTRO ?= "ignored"
+PACKAGE_CLASSES ?= "package_rpm"
+EXTRA_IMAGE_FEATURES ?= "debug-tweaks"
+EXTRA_IMAGE_FEATURES += "tools-sdk"
+IMAGE_INSTALL:append = " htop"
+BB_DISKMON_DIRS ??= "\
+    STOPTASKS,${TMPDIR},1G,100K \
+    ABORT,${TMPDIR},100M,1K"
+DL_DIR ?= "${TOPDIR}/../downloads"
+CONF_VERSION = "2"
+"#,
+        );
+
+        assert_eq!(get(&vars, "MACHINE"), Some("raspberrypi4-64"));
+        assert_eq!(get(&vars, "DISTRO"), Some("poky"));
+        assert_eq!(
+            get(&vars, "EXTRA_IMAGE_FEATURES"),
+            Some("debug-tweaks tools-sdk")
+        );
+        assert_eq!(get(&vars, "IMAGE_INSTALL"), None);
+        assert_eq!(
+            get(&vars, "BB_DISKMON_DIRS"),
+            Some("    STOPTASKS,${TMPDIR},1G,100K     ABORT,${TMPDIR},100M,1K")
+        );
+        assert_eq!(get(&vars, "DL_DIR"), Some("${TOPDIR}/../downloads"));
+        assert_eq!(get(&vars, "CONF_VERSION"), Some("2"));
+    }
+
+    #[test]
+    fn test_conf_defaults() {
+        let (vars, defaults) = parse_conf_assignments(
+            r#"
+MACHINE ??= "qemux86-64"
+DISTRO ?= "poky"
+DISTRO += "extra"
+DL_DIR ?= "/downloads"
+DL_DIR = "/srv/downloads"
+"#,
+        );
+
+        assert_eq!(get(&vars, "DISTRO"), Some("poky extra"));
+        assert_eq!(defaults, vec!["MACHINE", "DISTRO"]);
+    }
+
+    #[test]
+    fn test_yocto_bblayers_conf() {
+        let vars = parse_assignments(
+            r#"
+POKY_BBLAYERS_CONF_VERSION = "2"
+BBPATH = "${TOPDIR}"
+BBFILES ?= ""
+BBLAYERS ?= " \
+  /home/dev/poky/meta \
+  /home/dev/poky/meta-poky \
+  /home/dev/poky/meta-yocto-bsp \
+  "
+BBLAYERS += "/home/dev/meta-raspberrypi"
+"#,
+        );
+
+        let layers: Vec<&str> = get(&vars, "BBLAYERS").unwrap().split_whitespace().collect();
+        assert_eq!(
+            layers,
+            vec![
+                "/home/dev/poky/meta",
+                "/home/dev/poky/meta-poky",
+                "/home/dev/poky/meta-yocto-bsp",
+                "/home/dev/meta-raspberrypi",
+            ]
+        );
+        assert_eq!(get(&vars, "BBFILES"), Some(""));
+    }
+
+    #[test]
+    fn test_parameter_expansion_forms() {
+        let vars = parse_assignments(
+            r#"
+EMPTY=
+SET=value
+A=${UNSET:-fallback}
+B=${EMPTY-kept}
+C=${SET:+alt}
+D=${UNSET:+alt}
+E="${UNSET}/x"
+F+=one
+F+=two
+"#,
+        );
+
+        assert_eq!(get(&vars, "A"), Some("fallback"));
+        assert_eq!(get(&vars, "B"), Some(""));
+        assert_eq!(get(&vars, "C"), Some("alt"));
+        assert_eq!(get(&vars, "D"), Some(""));
+        assert_eq!(get(&vars, "E"), Some("${UNSET}/x"));
+        assert_eq!(get(&vars, "F"), Some("onetwo"));
+    }
+}
@@ -1,10 +1,22 @@
 //! BuildScript collector - parses config files to extract build targets
 
+use super::assignments::parse_assignments;
+use super::entry_point::parse_entry_point_commands;
 use super::traits::Collector;
-use crate::config::Config;
+use crate::config::{Config, TargetConfig};
 use crate::context::{BuildTarget, Context};
+use serde::Deserialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Directories searched for `AndroidProducts.mk` in AOSP trees
+const LUNCH_SEARCH_DIRS: &[&str] = &["device", "vendor"];
+
+/// How deep below `device/` and `vendor/` to look for `AndroidProducts.mk`
+const LUNCH_SEARCH_DEPTH: usize = 4;
+
+/// Upper bound on discovered lunch choices
+const MAX_LUNCH_CHOICES: usize = 50;
 
 /// BuildScript collector
 #[derive(Debug, Default)]
@@ -16,12 +28,24 @@ impl Collector for BuildCollector {
     }
 
     fn is_enabled(&self, config: &Config) -> bool {
-        config.scripts.is_some()
+        config.scripts.is_some() || config.targets.is_some() || is_aosp(config)
     }
 
     fn collect(&self, config: &Config, ctx: &mut Context) {
         ctx.targets = collect_build_targets(config);
 
+        if is_aosp(config) {
+            for target in discover_lunch_targets(Path::new(".")) {
+                let known = ctx
+                    .targets
+                    .iter()
+                    .any(|t| lunch_matches(&t.lunch_target, &target.lunch_target));
+                if !known {
+                    ctx.targets.push(target);
+                }
+            }
+        }
+
         // Also parse entry point commands if available
         if let Some(scripts) = &config.scripts {
             if let Some(entry) = &scripts.entry_point {
@@ -31,9 +55,14 @@ impl Collector for BuildCollector {
     }
 }
 
-/// Collect build targets from config files
+/// Collect build targets from `[[targets]]` tables and config files
 fn collect_build_targets(config: &Config) -> Vec<BuildTarget> {
-    let mut targets = Vec::new();
+    let mut targets: Vec<BuildTarget> = config
+        .targets
+        .iter()
+        .flatten()
+        .filter_map(target_from_config)
+        .collect();
 
     let scripts_config = match &config.scripts {
         Some(sc) => sc,
@@ -45,44 +74,111 @@ fn collect_build_targets(config: &Config) -> Vec<BuildTarget> {
         None => return targets,
     };
 
+    let extract_vars = scripts_config.extract_vars.clone().unwrap_or_default();
     let pattern = scripts_config.config_pattern.as_deref().unwrap_or("*.conf");
     let full_pattern = format!("{}/{}", config_dir, pattern);
 
     if let Ok(entries) = glob::glob(&full_pattern) {
         for entry in entries.flatten() {
-            if let Some(target) = parse_config_file(&entry) {
-                targets.push(target);
-            }
+            targets.extend(parse_target_file(&entry, &extract_vars));
         }
     }
 
     targets
 }
 
-/// Parse a single config file into a BuildTarget
-fn parse_config_file(path: &Path) -> Option<BuildTarget> {
+/// Layouts accepted for structured (JSON/YAML/TOML) target files
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TargetFile {
+    List(Vec<TargetConfig>),
+    Wrapped { targets: Vec<TargetConfig> },
+    Single(TargetConfig),
+}
+
+/// Parse a target file, choosing the format from its extension
+fn parse_target_file(path: &Path, extract_vars: &[String]) -> Vec<BuildTarget> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let parsed: Option<TargetFile> = match ext {
+        "json" => fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok()),
+        "yaml" | "yml" => fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_yaml::from_str(&c).ok()),
+        "toml" => fs::read_to_string(path)
+            .ok()
+            .and_then(|c| toml::from_str(&c).ok()),
+        _ => return parse_config_file(path, extract_vars).into_iter().collect(),
+    };
+
+    match parsed {
+        Some(TargetFile::List(list)) | Some(TargetFile::Wrapped { targets: list }) => list
+            .iter()
+            .filter_map(target_from_config)
+            .collect(),
+        Some(TargetFile::Single(t)) => target_from_config(&t).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Convert a structured target definition; without a non-empty `name` it is
+/// not a target (any JSON/YAML object would otherwise deserialize as one)
+fn target_from_config(config: &TargetConfig) -> Option<BuildTarget> {
+    let name = config.name.as_deref().filter(|n| !n.is_empty())?;
+
+    Some(BuildTarget {
+        name: name.to_string(),
+        description: config.description.clone().unwrap_or_default(),
+        container_name: config.container_name.clone().unwrap_or_default(),
+        lunch_target: config.lunch_target.clone().unwrap_or_default(),
+        can_emulator: config.can_emulator.unwrap_or(false),
+        can_flash: config.can_flash.unwrap_or(false),
+        extra: config
+            .extra
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Parse a single shell-style config file into a BuildTarget
+///
+/// Variables named in `extract_vars` are captured in that order. Values may
+/// reference variables assigned earlier in the same file as `$VAR` or `${VAR}`.
+fn parse_config_file(path: &Path, extract_vars: &[String]) -> Option<BuildTarget> {
     let content = fs::read_to_string(path).ok()?;
     let mut target = BuildTarget::default();
+    let vars = parse_assignments(&content);
 
-    for line in content.lines() {
-        let line = line.trim();
-        if line.starts_with('#') || line.is_empty() {
-            continue;
-        }
-
-        if let Some((key, value)) = parse_var_assignment(line) {
-            match key.as_str() {
-                "TARGET_NAME" => target.name = value,
-                "TARGET_DESCRIPTION" => target.description = value,
-                "CONTAINER_NAME" => target.container_name = value,
-                "LUNCH_TARGET" => target.lunch_target = value,
-                "CAN_EMULATOR" => target.can_emulator = value == "true",
-                "CAN_FLASH" => target.can_flash = value == "true",
-                _ => {}
-            }
+    for (key, value) in &vars {
+        match key.as_str() {
+            "TARGET_NAME" => target.name = value.clone(),
+            "TARGET_DESCRIPTION" => target.description = value.clone(),
+            "CONTAINER_NAME" => target.container_name = value.clone(),
+            "LUNCH_TARGET" => target.lunch_target = value.clone(),
+            "CAN_EMULATOR" => target.can_emulator = value == "true",
+            "CAN_FLASH" => target.can_flash = value == "true",
+            _ => {}
         }
     }
 
+    target.variables = extract_vars
+        .iter()
+        .filter_map(|name| {
+            vars.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| (name.clone(), value.clone()))
+        })
+        .collect();
+
     if target.name.is_empty() {
         target.name = path
             .file_stem()
@@ -94,38 +190,290 @@ fn parse_config_file(path: &Path) -> Option<BuildTarget> {
     Some(target)
 }
 
-/// Parse a variable assignment line (KEY=value)
-fn parse_var_assignment(line: &str) -> Option<(String, String)> {
-    let parts: Vec<&str> = line.splitn(2, '=').collect();
-    if parts.len() != 2 {
-        return None;
+fn is_aosp(config: &Config) -> bool {
+    config
+        .project
+        .as_ref()
+        .and_then(|p| p.project_type.as_deref())
+        .is_some_and(|t| t.eq_ignore_ascii_case("aosp"))
+}
+
+/// Discover lunch choices from `COMMON_LUNCH_CHOICES` in `AndroidProducts.mk` files
+///
+/// Accepts both `product-variant` and the newer `product-release-variant` form.
+fn discover_lunch_targets(root: &Path) -> Vec<BuildTarget> {
+    let mut product_files = Vec::new();
+    for dir in LUNCH_SEARCH_DIRS {
+        find_android_products(&root.join(dir), LUNCH_SEARCH_DEPTH, &mut product_files);
     }
+    product_files.sort();
+
+    let mut targets: Vec<BuildTarget> = Vec::new();
+    for path in product_files {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let choices = parse_assignments(&content)
+            .into_iter()
+            .find(|(name, _)| name == "COMMON_LUNCH_CHOICES")
+            .map(|(_, value)| value)
+            .unwrap_or_default();
+
+        let source = path
+            .parent()
+            .and_then(|p| p.strip_prefix(root).ok())
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
 
-    let key = parts[0].trim().to_string();
-    let value = parts[1]
-        .trim()
-        .trim_matches('"')
-        .trim_matches('\'')
-        .to_string();
+        for choice in choices.split_whitespace() {
+            if targets.iter().any(|t| t.lunch_target == choice) {
+                continue;
+            }
+            let product = choice.split('-').next().unwrap_or(choice);
+            targets.push(BuildTarget {
+                name: choice.to_string(),
+                description: source.clone(),
+                lunch_target: choice.to_string(),
+                can_flash: !is_virtual_product(product),
+                can_emulator: is_virtual_product(product),
+                ..Default::default()
+            });
+        }
+    }
 
-    Some((key, value))
+    targets.truncate(MAX_LUNCH_CHOICES);
+    targets
 }
 
-/// Parse entry point script to extract available commands
-pub fn parse_entry_point_commands(entry_point: &str) -> Vec<String> {
-    let mut commands = Vec::new();
+fn find_android_products(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
 
-    if let Ok(content) = fs::read_to_string(entry_point) {
-        for line in content.lines() {
-            let line = line.trim();
-            if line.contains("./") && line.contains(".sh ") {
-                commands.push(line.to_string());
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() {
+            if depth > 0 && !hidden {
+                find_android_products(&path, depth - 1, out);
             }
+        } else if entry.file_name() == "AndroidProducts.mk" {
+            out.push(path);
+        }
+    }
+}
+
+/// Emulator and Cuttlefish products can run without hardware
+fn is_virtual_product(product: &str) -> bool {
+    ["sdk_", "aosp_cf_", "cf_", "emulator_", "gsi_"]
+        .iter()
+        .any(|prefix| product.starts_with(prefix) || product.contains(&format!("_{}", prefix)))
+}
+
+/// Compare lunch targets, treating `product-variant` as equal to any
+/// `product-<release>-variant`
+fn lunch_matches(a: &str, b: &str) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+
+    let strip_release = |s: &str| -> String {
+        let parts: Vec<&str> = s.split('-').collect();
+        match parts.as_slice() {
+            [product, _, variant] => format!("{}-{}", product, variant),
+            _ => s.to_string(),
         }
+    };
+    strip_release(a) == strip_release(b)
+}
+
+/// Mark the explicitly selected target, or the one matching the most recent lunch, as active
+pub fn mark_active_target(ctx: &mut Context) {
+    if let Some(selected) = &ctx.active_target {
+        if let Some(target) = ctx.targets.iter_mut().find(|t| t.name == selected.name) {
+            target.active = true;
+            return;
+        }
+    }
+
+    let lunch = match ctx.environments.iter().find(|e| !e.lunch_target.is_empty()) {
+        Some(env) => env.lunch_target.clone(),
+        None => return,
+    };
+
+    // Prefer an exact match over one that only differs by release config
+    let pos = ctx
+        .targets
+        .iter()
+        .position(|t| t.lunch_target == lunch)
+        .or_else(|| {
+            ctx.targets
+                .iter()
+                .position(|t| lunch_matches(&t.lunch_target, &lunch))
+        });
+    if let Some(pos) = pos {
+        ctx.targets[pos].active = true;
     }
+}
 
-    commands.sort();
-    commands.dedup();
-    commands.truncate(10);
-    commands
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("ck-build-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_inline_targets_keep_extra_fields() {
+        let config: Config = toml::from_str(
+            r#"
+            [[targets]]
+            name = "emu"
+            container = "aosp-build"
+            lunch_target = "sdk_phone64_x86_64-userdebug"
+            can_emulator = true
+            jobs = 32
+            notes = "use ccache"
+        "#,
+        )
+        .unwrap();
+
+        let targets = collect_build_targets(&config);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].container_name, "aosp-build");
+        assert!(targets[0].can_emulator);
+        assert_eq!(targets[0].extra.get("jobs").map(String::as_str), Some("32"));
+        assert_eq!(
+            targets[0].extra.get("notes").map(String::as_str),
+            Some("use ccache")
+        );
+    }
+
+    #[test]
+    fn test_parse_json_and_yaml_target_files() {
+        let json = write_temp(
+            "rpi4.json",
+            r#"{"name": "rpi4", "description": "Raspberry Pi 4", "can_flash": true, "board": "rpi4"}"#,
+        );
+        let targets = parse_target_file(&json, &[]);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "rpi4");
+        assert!(targets[0].can_flash);
+        assert_eq!(targets[0].extra.get("board").map(String::as_str), Some("rpi4"));
+
+        let yaml = write_temp(
+            "targets.yaml",
+            "targets:\n  - name: a\n    lunch: aosp_a-userdebug\n  - name: b\n    tags: [x, y]\n",
+        );
+        let targets = parse_target_file(&yaml, &[]);
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].lunch_target, "aosp_a-userdebug");
+        assert_eq!(targets[1].extra.get("tags").map(String::as_str), Some(r#"["x","y"]"#));
+
+        // Unrelated files matched by the glob are not targets
+        for (file, content) in [
+            ("package.json", r#"{"version": "1.0.0", "scripts": {}}"#),
+            ("unnamed.yaml", "name: \"\"\nlunch: aosp_a-userdebug\n"),
+            ("ci.yml", "name: [build, test]\non: push\n"),
+        ] {
+            assert!(parse_target_file(&write_temp(file, content), &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_extract_vars_with_expansion() {
+        let conf = write_temp(
+            "board.conf",
+            "TARGET_NAME=rpi4\nBOARD=rpi4\nOUT_ROOT=/work/out\nPRODUCT_OUT=${OUT_ROOT}/$BOARD/images\nKERNEL_DIR='$SRC/kernel'\nIGNORED=1\n",
+        );
+        let extract = ["PRODUCT_OUT", "KERNEL_DIR", "BOARD", "MISSING"].map(String::from);
+        let target = parse_config_file(&conf, &extract).unwrap();
+        assert_eq!(
+            target.variables,
+            vec![
+                ("PRODUCT_OUT".to_string(), "/work/out/rpi4/images".to_string()),
+                ("KERNEL_DIR".to_string(), "$SRC/kernel".to_string()),
+                ("BOARD".to_string(), "rpi4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_lunch_targets() {
+        let root = std::env::temp_dir().join(format!("ck-lunch-{}", std::process::id()));
+        let cf = root.join("device/google/cuttlefish");
+        let pixel = root.join("device/google/shusky");
+        fs::create_dir_all(&cf).unwrap();
+        fs::create_dir_all(&pixel).unwrap();
+        fs::write(
+            cf.join("AndroidProducts.mk"),
+            "PRODUCT_MAKEFILES := \\\n    $(LOCAL_DIR)/aosp_cf_x86_64_phone.mk\n\nCOMMON_LUNCH_CHOICES := \\\n    aosp_cf_x86_64_phone-trunk_staging-userdebug \\\n    aosp_cf_x86_64_phone-trunk_staging-eng\n",
+        )
+        .unwrap();
+        fs::write(
+            pixel.join("AndroidProducts.mk"),
+            "COMMON_LUNCH_CHOICES := aosp_shiba-userdebug\nCOMMON_LUNCH_CHOICES += aosp_husky-userdebug\n",
+        )
+        .unwrap();
+
+        let mut ctx = Context {
+            targets: discover_lunch_targets(&root),
+            ..Default::default()
+        };
+        let names: Vec<&str> = ctx.targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "aosp_cf_x86_64_phone-trunk_staging-userdebug",
+                "aosp_cf_x86_64_phone-trunk_staging-eng",
+                "aosp_shiba-userdebug",
+                "aosp_husky-userdebug",
+            ]
+        );
+        assert!(ctx.targets[0].can_emulator);
+        assert!(!ctx.targets[0].can_flash);
+        assert!(!ctx.targets[2].can_emulator);
+        assert!(ctx.targets[2].can_flash);
+        assert_eq!(ctx.targets[2].description, "device/google/shusky");
+
+        ctx.environments = vec![crate::context::ActiveEnvironment {
+            lunch_target: "aosp_husky-ap2a-userdebug".to_string(),
+            ..Default::default()
+        }];
+        mark_active_target(&mut ctx);
+        let active: Vec<&str> = ctx
+            .targets
+            .iter()
+            .filter(|t| t.active)
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(active, vec!["aosp_husky-userdebug"]);
+
+        // An explicit selection wins over the lunch target in history
+        for target in &mut ctx.targets {
+            target.active = false;
+        }
+        ctx.active_target = Some(crate::context::ActiveTarget {
+            name: "aosp_shiba-userdebug".to_string(),
+            ..Default::default()
+        });
+        mark_active_target(&mut ctx);
+        let active: Vec<&str> = ctx
+            .targets
+            .iter()
+            .filter(|t| t.active)
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(active, vec!["aosp_shiba-userdebug"]);
+    }
 }
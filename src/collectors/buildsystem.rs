@@ -0,0 +1,420 @@
+//! Build system collector - CMake presets and build dirs, Makefile, justfile and Taskfile
+
+use super::format_command;
+use super::traits::Collector;
+use crate::config::Config;
+use crate::context::{BuildSystemInfo, CmakeBuildDir, CmakePreset, Context};
+use std::fs;
+use std::path::Path;
+
+/// Upper bound on commands taken from each Makefile/justfile/Taskfile
+const MAX_COMMANDS_PER_FILE: usize = 20;
+
+/// CMake / Make / Ninja / just / Task collector
+#[derive(Debug, Default)]
+pub struct BuildSystemCollector;
+
+impl Collector for BuildSystemCollector {
+    fn name(&self) -> &'static str {
+        "buildsystem"
+    }
+
+    fn is_enabled(&self, config: &Config) -> bool {
+        config
+            .buildsystem
+            .as_ref()
+            .and_then(|b| b.enabled)
+            .unwrap_or(true)
+    }
+
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let root = config
+            .buildsystem
+            .as_ref()
+            .and_then(|b| b.root.clone())
+            .unwrap_or_else(|| ".".to_string());
+        let root = Path::new(&root);
+
+        let presets = read_cmake_presets(root);
+        let build_dirs = find_build_dirs(root, &presets);
+        if !presets.is_empty() || !build_dirs.is_empty() {
+            ctx.build_system = Some(BuildSystemInfo {
+                cmake_presets: presets,
+                build_dirs,
+            });
+        }
+
+        let commands = ["Makefile", "makefile", "GNUmakefile"]
+            .iter()
+            .find_map(|name| fs::read_to_string(root.join(name)).ok())
+            .map(|content| parse_make_targets(&content))
+            .into_iter()
+            .chain(
+                ["justfile", "Justfile", ".justfile"]
+                    .iter()
+                    .find_map(|name| fs::read_to_string(root.join(name)).ok())
+                    .map(|content| parse_just_recipes(&content)),
+            )
+            .chain(
+                [
+                    "Taskfile.yml",
+                    "Taskfile.yaml",
+                    "taskfile.yml",
+                    "taskfile.yaml",
+                ]
+                .iter()
+                .find_map(|name| fs::read_to_string(root.join(name)).ok())
+                .map(|content| parse_taskfile(&content)),
+            );
+
+        for mut file_commands in commands {
+            file_commands.truncate(MAX_COMMANDS_PER_FILE);
+            for command in file_commands {
+                if !ctx.available_commands.contains(&command) {
+                    ctx.available_commands.push(command);
+                }
+            }
+        }
+    }
+}
+
+/// Visible presets from `CMakePresets.json` and `CMakeUserPresets.json`
+fn read_cmake_presets(root: &Path) -> Vec<CmakePreset> {
+    let mut presets = Vec::new();
+
+    for file in ["CMakePresets.json", "CMakeUserPresets.json"] {
+        let json: serde_json::Value = match fs::read_to_string(root.join(file))
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+        {
+            Some(json) => json,
+            None => continue,
+        };
+
+        for (key, kind) in [
+            ("configurePresets", "configure"),
+            ("buildPresets", "build"),
+            ("testPresets", "test"),
+        ] {
+            let entries = json.get(key).and_then(|v| v.as_array());
+            for entry in entries.into_iter().flatten() {
+                if entry.get("hidden").and_then(|h| h.as_bool()) == Some(true) {
+                    continue;
+                }
+                let text = |field: &str| {
+                    entry
+                        .get(field)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let name = text("name");
+                if name.is_empty() {
+                    continue;
+                }
+
+                let description = [text("description"), text("displayName")]
+                    .into_iter()
+                    .find(|d| !d.is_empty())
+                    .unwrap_or_default();
+                let binary_dir = text("binaryDir")
+                    .replace("${sourceDir}/", "")
+                    .replace("${sourceDir}", ".")
+                    .replace("${presetName}", &name);
+
+                presets.push(CmakePreset {
+                    kind: kind.to_string(),
+                    description,
+                    generator: text("generator"),
+                    binary_dir,
+                    configure_preset: text("configurePreset"),
+                    name,
+                });
+            }
+        }
+    }
+
+    presets
+}
+
+/// Configured build directories: top-level `build*` dirs and preset binary dirs
+fn find_build_dirs(root: &Path, presets: &[CmakePreset]) -> Vec<CmakeBuildDir> {
+    let mut candidates: Vec<String> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with("build") || name.starts_with("cmake-build"))
+                .collect()
+        })
+        .unwrap_or_default();
+    candidates.sort();
+
+    for preset in presets {
+        if !preset.binary_dir.is_empty() && !candidates.contains(&preset.binary_dir) {
+            candidates.push(preset.binary_dir.clone());
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|dir| read_build_dir(&root.join(&dir), &dir))
+        .collect()
+}
+
+/// Generator and build type of a configured CMake (or plain Ninja) build directory
+fn read_build_dir(path: &Path, display: &str) -> Option<CmakeBuildDir> {
+    if let Ok(cache) = fs::read_to_string(path.join("CMakeCache.txt")) {
+        let value = |key: &str| {
+            cache
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once('=')?;
+                    (name.split(':').next() == Some(key)).then(|| value.trim().to_string())
+                })
+                .unwrap_or_default()
+        };
+        return Some(CmakeBuildDir {
+            path: display.to_string(),
+            generator: value("CMAKE_GENERATOR"),
+            build_type: value("CMAKE_BUILD_TYPE"),
+        });
+    }
+
+    // Ninja build dir generated by something other than CMake (e.g. meson, gn)
+    path.join("build.ninja").exists().then(|| CmakeBuildDir {
+        path: display.to_string(),
+        generator: "Ninja".to_string(),
+        build_type: String::new(),
+    })
+}
+
+/// `.PHONY` targets of a Makefile, with `target: ## description` help text
+fn parse_make_targets(content: &str) -> Vec<String> {
+    let joined = content.replace("\\\n", " ");
+    let mut phony: Vec<&str> = Vec::new();
+
+    for line in joined.lines() {
+        if let Some(targets) = line.strip_prefix(".PHONY:") {
+            for target in targets.split_whitespace() {
+                if !target.contains(['%', '$']) && !phony.contains(&target) {
+                    phony.push(target);
+                }
+            }
+        }
+    }
+
+    phony
+        .into_iter()
+        .map(|target| {
+            let description = joined
+                .lines()
+                .find_map(|line| {
+                    let rest = line.strip_prefix(target)?.trim_start().strip_prefix(':')?;
+                    rest.split_once("##").map(|(_, d)| d.trim())
+                })
+                .unwrap_or_default();
+            format_command(format!("make {}", target), description)
+        })
+        .collect()
+}
+
+/// Public recipes of a justfile, with their parameters and doc comments
+fn parse_just_recipes(content: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    let mut comment = String::new();
+    let mut private = false;
+
+    for line in content.lines() {
+        if line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+            if line.trim().is_empty() {
+                comment.clear();
+                private = false;
+            }
+            continue;
+        }
+        if let Some(text) = line.strip_prefix('#') {
+            comment = text.trim().to_string();
+            continue;
+        }
+        if line.starts_with('[') {
+            private |= line.contains("private");
+            continue;
+        }
+
+        let header = match line.split_once(':') {
+            // `:=` is a variable assignment, not a recipe
+            Some((header, rest)) if !rest.starts_with('=') => header,
+            _ => {
+                comment.clear();
+                continue;
+            }
+        };
+        let mut words = header.split_whitespace();
+        let name = words.next().unwrap_or_default().trim_start_matches('@');
+        let keyword = ["set", "alias", "export", "import", "mod"].contains(&name);
+
+        if !keyword && !name.is_empty() && !name.starts_with('_') && !private {
+            let params: Vec<String> = words
+                .map(|p| match p.split_once('=') {
+                    Some((name, _)) => format!("[{}]", name.trim_start_matches(['+', '*', '$'])),
+                    None if p.starts_with(['+', '*']) => {
+                        format!("<{}>...", p.trim_start_matches(['+', '*', '$']))
+                    }
+                    None => format!("<{}>", p.trim_start_matches('$')),
+                })
+                .collect();
+            let command = if params.is_empty() {
+                format!("just {}", name)
+            } else {
+                format!("just {} {}", name, params.join(" "))
+            };
+            commands.push(format_command(command, &comment));
+        }
+
+        comment.clear();
+        private = false;
+    }
+
+    commands
+}
+
+/// Tasks from a go-task Taskfile, with their `desc`
+fn parse_taskfile(content: &str) -> Vec<String> {
+    let yaml: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(yaml) => yaml,
+        Err(_) => return Vec::new(),
+    };
+    let tasks = match yaml.get("tasks").and_then(|t| t.as_mapping()) {
+        Some(tasks) => tasks,
+        None => return Vec::new(),
+    };
+
+    tasks
+        .iter()
+        .filter_map(|(name, task)| {
+            let name = name.as_str()?;
+            if task.get("internal").and_then(|i| i.as_bool()) == Some(true) {
+                return None;
+            }
+            let description = task.get("desc").and_then(|d| d.as_str()).unwrap_or("");
+            Some(format_command(format!("task {}", name), description))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_make_targets() {
+        let makefile = "\
+.PHONY: all flash \\\n\tclean %.o\n\
+all: firmware.bin ## Build the firmware\n\
+flash: all ## Flash over SWD\n\
+clean:\n\trm -rf build\n";
+
+        assert_eq!(
+            parse_make_targets(makefile),
+            vec![
+                "make all  # Build the firmware",
+                "make flash  # Flash over SWD",
+                "make clean",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_just_recipes() {
+        let justfile = "\
+set shell := [\"bash\", \"-c\"]
+board := \"nucleo\"
+
+# Build for a board
+build target=board:
+    cmake --build build
+
+# Flash the image
+flash port +args:
+    ./flash.sh {{port}} {{args}}
+
+[private]
+helper:
+    echo hi
+
+_hidden:
+    echo no
+";
+
+        assert_eq!(
+            parse_just_recipes(justfile),
+            vec![
+                "just build [target]  # Build for a board",
+                "just flash <port> <args>...  # Flash the image",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_taskfile() {
+        let taskfile = "\
+version: '3'
+tasks:
+  build:
+    desc: Build all services
+    cmds: [go build ./...]
+  lint:
+    cmds: [golangci-lint run]
+  setup:
+    internal: true
+";
+
+        assert_eq!(
+            parse_taskfile(taskfile),
+            vec!["task build  # Build all services", "task lint"]
+        );
+    }
+
+    #[test]
+    fn test_cmake_presets_and_build_dirs() {
+        let root = std::env::temp_dir().join(format!("ck-cmake-{}", std::process::id()));
+        fs::create_dir_all(root.join("out/debug")).unwrap();
+        fs::write(
+            root.join("CMakePresets.json"),
+            r#"{
+  "version": 6,
+  "configurePresets": [
+    {"name": "base", "hidden": true, "generator": "Ninja"},
+    {"name": "debug", "inherits": "base", "displayName": "Debug", "binaryDir": "${sourceDir}/out/${presetName}"}
+  ],
+  "buildPresets": [{"name": "debug-build", "configurePreset": "debug"}]
+}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("out/debug/CMakeCache.txt"),
+            "# comment\nCMAKE_BUILD_TYPE:STRING=Debug\nCMAKE_GENERATOR:INTERNAL=Ninja\n",
+        )
+        .unwrap();
+
+        let presets = read_cmake_presets(&root);
+        let names: Vec<(&str, &str)> = presets
+            .iter()
+            .map(|p| (p.name.as_str(), p.kind.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("debug", "configure"), ("debug-build", "build")]
+        );
+        assert_eq!(presets[0].binary_dir, "out/debug");
+        assert_eq!(presets[1].configure_preset, "debug");
+
+        let dirs = find_build_dirs(&root, &presets);
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs[0].path, "out/debug");
+        assert_eq!(dirs[0].generator, "Ninja");
+        assert_eq!(dirs[0].build_type, "Debug");
+    }
+}
@@ -0,0 +1,313 @@
+//! Cargo collector - workspace members, binaries, tests, features and toolchain
+
+use super::traits::Collector;
+use crate::config::Config;
+use crate::context::{BuildTarget, CargoPackage, CargoWorkspace, Context};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Cargo workspace collector
+#[derive(Debug, Default)]
+pub struct CargoCollector;
+
+impl Collector for CargoCollector {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn is_enabled(&self, config: &Config) -> bool {
+        // Enabled by default for Cargo projects, can be disabled via config
+        config
+            .cargo
+            .as_ref()
+            .and_then(|c| c.enabled)
+            .unwrap_or_else(|| {
+                config
+                    .project
+                    .as_ref()
+                    .and_then(|p| p.project_type.as_deref())
+                    .is_some_and(|t| t == "cargo" || t == "rust")
+            })
+    }
+
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let root = config
+            .cargo
+            .as_ref()
+            .and_then(|c| c.root.clone())
+            .unwrap_or_else(|| ".".to_string());
+
+        let workspace = match read_workspace(Path::new(&root)) {
+            Some(workspace) => workspace,
+            None => return,
+        };
+
+        for target in workspace_targets(&workspace) {
+            if !ctx.targets.iter().any(|t| t.name == target.name) {
+                ctx.targets.push(target);
+            }
+        }
+        ctx.cargo = Some(workspace);
+    }
+}
+
+/// Read the workspace rooted at `root` (a single package counts as a workspace of one)
+fn read_workspace(root: &Path) -> Option<CargoWorkspace> {
+    let manifest = read_manifest(&root.join("Cargo.toml"))?;
+    let mut packages = Vec::new();
+
+    if manifest.get("package").is_some() {
+        packages.extend(read_package(root, &manifest));
+    }
+
+    if let Some(workspace) = manifest.get("workspace") {
+        let excluded: Vec<PathBuf> = string_array(workspace.get("exclude"))
+            .iter()
+            .map(|p| root.join(p))
+            .collect();
+
+        for pattern in string_array(workspace.get("members")) {
+            let mut dirs: Vec<PathBuf> = glob::glob(&root.join(&pattern).to_string_lossy())
+                .map(|paths| paths.flatten().collect())
+                .unwrap_or_default();
+            dirs.sort();
+
+            for dir in dirs {
+                if dir == root || excluded.contains(&dir) {
+                    continue;
+                }
+                if let Some(member) = read_manifest(&dir.join("Cargo.toml")) {
+                    packages.extend(read_package(&dir, &member));
+                }
+            }
+        }
+    }
+
+    if packages.is_empty() {
+        return None;
+    }
+
+    Some(CargoWorkspace {
+        packages,
+        target_dir: target_dir(root),
+        toolchain: toolchain(root),
+    })
+}
+
+fn read_manifest(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn string_array(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|i| i.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names of explicit `[[bin]]` / `[[test]]` entries
+fn table_names(manifest: &toml::Value, key: &str) -> Vec<String> {
+    manifest
+        .get(key)
+        .and_then(|v| v.as_array())
+        .map(|tables| {
+            tables
+                .iter()
+                .filter_map(|t| t.get("name")?.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Rust file stems directly in `dir` plus subdirectories containing `main.rs`
+fn auto_targets(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    let path = e.path();
+                    if path.is_dir() && path.join("main.rs").exists() {
+                        Some(e.file_name().to_string_lossy().to_string())
+                    } else if path.extension().is_some_and(|ext| ext == "rs") {
+                        path.file_stem().map(|s| s.to_string_lossy().to_string())
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Build a package from its manifest, including auto-discovered targets
+fn read_package(dir: &Path, manifest: &toml::Value) -> Option<CargoPackage> {
+    let package = manifest.get("package")?;
+    let name = package.get("name")?.as_str()?.to_string();
+    let auto = |key: &str| package.get(key).and_then(|v| v.as_bool()).unwrap_or(true);
+
+    let mut binaries = table_names(manifest, "bin");
+    if auto("autobins") {
+        if dir.join("src/main.rs").exists() && !binaries.contains(&name) {
+            binaries.insert(0, name.clone());
+        }
+        for bin in auto_targets(&dir.join("src/bin")) {
+            if !binaries.contains(&bin) {
+                binaries.push(bin);
+            }
+        }
+    }
+
+    let mut tests = table_names(manifest, "test");
+    if auto("autotests") {
+        for test in auto_targets(&dir.join("tests")) {
+            if !tests.contains(&test) {
+                tests.push(test);
+            }
+        }
+    }
+
+    let features = manifest
+        .get("features")
+        .and_then(|f| f.as_table())
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default();
+
+    Some(CargoPackage {
+        name,
+        path: dir.display().to_string(),
+        has_lib: dir.join("src/lib.rs").exists() || manifest.get("lib").is_some(),
+        binaries,
+        tests,
+        features,
+    })
+}
+
+/// Target directory from `CARGO_TARGET_DIR`, `.cargo/config.toml` or the default
+fn target_dir(root: &Path) -> String {
+    if let Ok(dir) = std::env::var("CARGO_TARGET_DIR") {
+        return dir;
+    }
+
+    [".cargo/config.toml", ".cargo/config"]
+        .iter()
+        .filter_map(|file| read_manifest(&root.join(file)))
+        .find_map(|config| {
+            config
+                .get("build")?
+                .get("target-dir")?
+                .as_str()
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "target".to_string())
+}
+
+/// Toolchain channel from `rust-toolchain.toml` or a plain `rust-toolchain` file
+fn toolchain(root: &Path) -> String {
+    if let Some(file) = read_manifest(&root.join("rust-toolchain.toml"))
+        .or_else(|| read_manifest(&root.join("rust-toolchain")))
+    {
+        if let Some(channel) = file
+            .get("toolchain")
+            .and_then(|t| t.get("channel"))
+            .and_then(|c| c.as_str())
+        {
+            return channel.to_string();
+        }
+    }
+
+    // Legacy format: the file only contains the channel name
+    fs::read_to_string(root.join("rust-toolchain"))
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Present binaries and integration tests as build targets
+fn workspace_targets(workspace: &CargoWorkspace) -> Vec<BuildTarget> {
+    let mut targets = Vec::new();
+
+    for package in &workspace.packages {
+        let kinds = [("bin", &package.binaries), ("test", &package.tests)];
+        for (kind, names) in kinds {
+            for name in names {
+                let mut target = BuildTarget {
+                    name: name.clone(),
+                    description: format!("cargo {} in {}", kind, package.name),
+                    ..Default::default()
+                };
+                target
+                    .extra
+                    .insert("package".to_string(), package.name.clone());
+                target.extra.insert("kind".to_string(), kind.to_string());
+                targets.push(target);
+            }
+        }
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_workspace() {
+        let root = std::env::temp_dir().join(format!("ck-cargo-{}", std::process::id()));
+        let cli = root.join("crates/cli");
+        let core = root.join("crates/core");
+        fs::create_dir_all(cli.join("src/bin")).unwrap();
+        fs::create_dir_all(core.join("src")).unwrap();
+        fs::create_dir_all(core.join("tests")).unwrap();
+
+        fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"1.78.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            cli.join("Cargo.toml"),
+            "[package]\nname = \"tool\"\n\n[[bin]]\nname = \"toolctl\"\npath = \"src/ctl.rs\"\n\n[features]\ndefault = []\nserial = []\n",
+        )
+        .unwrap();
+        fs::write(cli.join("src/main.rs"), "").unwrap();
+        fs::write(cli.join("src/bin/migrate.rs"), "").unwrap();
+        fs::write(core.join("Cargo.toml"), "[package]\nname = \"core\"\n").unwrap();
+        fs::write(core.join("src/lib.rs"), "").unwrap();
+        fs::write(core.join("tests/roundtrip.rs"), "").unwrap();
+
+        let workspace = read_workspace(&root).unwrap();
+        assert_eq!(workspace.toolchain, "1.78.0");
+        assert_eq!(workspace.packages.len(), 2);
+
+        let tool = &workspace.packages[0];
+        assert_eq!(tool.name, "tool");
+        assert_eq!(tool.binaries, vec!["tool", "toolctl", "migrate"]);
+        assert_eq!(tool.features, vec!["default", "serial"]);
+
+        let core = &workspace.packages[1];
+        assert!(core.has_lib);
+        assert!(core.binaries.is_empty());
+        assert_eq!(core.tests, vec!["roundtrip"]);
+
+        let targets = workspace_targets(&workspace);
+        let names: Vec<&str> = targets.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["tool", "toolctl", "migrate", "roundtrip"]);
+        assert_eq!(
+            targets[3].extra.get("kind").map(String::as_str),
+            Some("test")
+        );
+    }
+}
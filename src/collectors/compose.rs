@@ -0,0 +1,341 @@
+//! Compose collector - services from compose files and whether they are running
+
+use super::traits::Collector;
+use crate::config::Config;
+use crate::context::{ComposeProject, ComposeService, ContainerInfo, Context};
+use std::fs;
+use std::path::Path;
+
+/// Base files looked for when `[compose] files` is not set, in compose's order
+const DEFAULT_COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+    "podman-compose.yaml",
+    "podman-compose.yml",
+];
+
+/// Docker Compose / podman-compose collector
+#[derive(Debug, Default)]
+pub struct ComposeCollector;
+
+impl Collector for ComposeCollector {
+    fn name(&self) -> &'static str {
+        "compose"
+    }
+
+    fn is_enabled(&self, config: &Config) -> bool {
+        config
+            .compose
+            .as_ref()
+            .and_then(|c| c.enabled)
+            .unwrap_or(true)
+    }
+
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let compose = config.compose.as_ref();
+        let files: Vec<String> = compose
+            .and_then(|c| c.files.clone())
+            .unwrap_or_else(|| default_compose_files(Path::new(".")));
+
+        let mut name = String::new();
+        let mut services: Vec<ComposeService> = Vec::new();
+        let mut read_files = Vec::new();
+        for file in files {
+            let content = match fs::read_to_string(&file) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            if let Some(file_name) = parse_compose_file(&content, &mut services) {
+                name = file_name;
+            }
+            read_files.push(file);
+        }
+        if services.is_empty() {
+            return;
+        }
+
+        // Same precedence as compose: the environment overrides the file's `name:`
+        let name = compose
+            .and_then(|c| c.project.clone())
+            .or_else(|| std::env::var("COMPOSE_PROJECT_NAME").ok())
+            .filter(|n| !n.is_empty())
+            .or_else(|| Some(name).filter(|n| !n.is_empty()))
+            .unwrap_or_else(default_project_name);
+
+        // Service containers are resolved by the container collector, which runs next
+        for service in &mut services {
+            service.state = "not running".to_string();
+        }
+
+        ctx.compose = Some(ComposeProject {
+            name,
+            files: read_files,
+            services,
+        });
+    }
+}
+
+/// The first base file found in `dir` and its `.override` file, as compose picks them
+fn default_compose_files(dir: &Path) -> Vec<String> {
+    let base = match DEFAULT_COMPOSE_FILES.iter().find(|f| dir.join(f).is_file()) {
+        Some(base) => base,
+        None => return Vec::new(),
+    };
+    let (stem, ext) = base.rsplit_once('.').unwrap_or((base, "yaml"));
+    let overrides = format!("{}.override.{}", stem, ext);
+
+    let mut files = vec![base.to_string()];
+    if dir.join(&overrides).is_file() {
+        files.push(overrides);
+    }
+    files
+}
+
+/// Merge the services of one compose file into `services`, returning its `name:`
+fn parse_compose_file(content: &str, services: &mut Vec<ComposeService>) -> Option<String> {
+    let yaml: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    let text = |value: Option<&serde_yaml::Value>| {
+        value
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let defined = yaml.get("services").and_then(|s| s.as_mapping());
+    for (key, definition) in defined.into_iter().flatten() {
+        let name = match key.as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        // `build: ./dir` or `build: {context: ./dir, dockerfile: ...}`
+        let build = definition.get("build").map(|b| {
+            b.as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| text(b.get("context")))
+        });
+        let profiles: Vec<String> = definition
+            .get("profiles")
+            .and_then(|p| p.as_sequence())
+            .map(|p| {
+                p.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let index = match services.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                services.push(ComposeService {
+                    name,
+                    ..Default::default()
+                });
+                services.len() - 1
+            }
+        };
+
+        // Later files override only the fields they set
+        let service = &mut services[index];
+        let image = text(definition.get("image"));
+        if !image.is_empty() {
+            service.image = image;
+        }
+        if let Some(build) = build {
+            service.build = if build.is_empty() {
+                ".".to_string()
+            } else {
+                build
+            };
+        }
+        let container_name = text(definition.get("container_name"));
+        if !container_name.is_empty() {
+            service.container_name = container_name;
+        }
+        if !profiles.is_empty() {
+            service.profiles = profiles;
+        }
+    }
+
+    yaml.get("name")
+        .and_then(|n| n.as_str())
+        .map(str::to_string)
+}
+
+/// Compose's default project name: the directory name, lowercased
+fn default_project_name() -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().to_lowercase()))
+        .map(|name| {
+            name.chars()
+                .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Container names compose gives a service: `container_name`, or `<project>-<service>-1`
+/// (docker compose v2) and `<project>_<service>_1` (v1, podman-compose)
+fn container_candidates(project: &str, service: &ComposeService) -> Vec<String> {
+    if service.container_name.is_empty() {
+        vec![
+            format!("{}-{}-1", project, service.name),
+            format!("{}_{}_1", project, service.name),
+        ]
+    } else {
+        vec![service.container_name.clone()]
+    }
+}
+
+/// Names the container collector should look up even when they are not running
+pub(super) fn compose_container_names(compose: &ComposeProject) -> Vec<String> {
+    compose
+        .services
+        .iter()
+        .flat_map(|service| container_candidates(&compose.name, service))
+        .collect()
+}
+
+/// Match every service of the project to its container
+pub(super) fn resolve_services(compose: &mut ComposeProject, containers: &[ContainerInfo]) {
+    for service in &mut compose.services {
+        resolve_container(&compose.name, service, containers);
+    }
+}
+
+/// Find the service's container by compose labels or name and record its state
+fn resolve_container(project: &str, service: &mut ComposeService, containers: &[ContainerInfo]) {
+    // docker compose and podman-compose both label their containers
+    let by_label = containers.iter().find(|c| {
+        c.labels
+            .get("com.docker.compose.project")
+            .map(String::as_str)
+            == Some(project)
+            && c.labels.get("com.docker.compose.service") == Some(&service.name)
+    });
+
+    let candidates = container_candidates(project, service);
+    let container = by_label.or_else(|| containers.iter().find(|c| candidates.contains(&c.name)));
+
+    match container {
+        Some(c) => {
+            service.container_name = c.name.clone();
+            service.state = if c.running { "running" } else { "stopped" }.to_string();
+            service.runtime = c.runtime.clone();
+        }
+        None => {
+            service.container_name = candidates[0].clone();
+            service.state = "not running".to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compose_with_override() {
+        let base = r#"
+name: robot
+services:
+  ros:
+    build:
+      context: ./docker/ros
+    profiles: [sim]
+  db:
+    image: postgres:16
+    container_name: robot-db
+"#;
+        let overrides = r#"
+services:
+  ros:
+    image: robot/ros:humble
+"#;
+
+        let mut services = Vec::new();
+        assert_eq!(
+            parse_compose_file(base, &mut services),
+            Some("robot".to_string())
+        );
+        assert_eq!(parse_compose_file(overrides, &mut services), None);
+
+        assert_eq!(services.len(), 2);
+        assert_eq!(services[0].name, "ros");
+        assert_eq!(services[0].image, "robot/ros:humble");
+        assert_eq!(services[0].build, "./docker/ros");
+        assert_eq!(services[0].profiles, vec!["sim"]);
+        assert_eq!(services[1].container_name, "robot-db");
+
+        let mut labels = std::collections::BTreeMap::new();
+        labels.insert(
+            "com.docker.compose.project".to_string(),
+            "robot".to_string(),
+        );
+        labels.insert("com.docker.compose.service".to_string(), "ros".to_string());
+        let mut project = ComposeProject {
+            name: "robot".to_string(),
+            services,
+            ..Default::default()
+        };
+        assert_eq!(
+            compose_container_names(&project),
+            vec!["robot-ros-1", "robot_ros_1", "robot-db"]
+        );
+
+        let containers = vec![
+            ContainerInfo {
+                name: "robot-ros-run-1a2b".to_string(),
+                runtime: "docker".to_string(),
+                running: true,
+                labels,
+                ..Default::default()
+            },
+            // Stopped, found by inspecting the names above
+            ContainerInfo {
+                name: "robot-db".to_string(),
+                runtime: "docker".to_string(),
+                ..Default::default()
+            },
+        ];
+        resolve_services(&mut project, &containers);
+        assert_eq!(project.services[0].container_name, "robot-ros-run-1a2b");
+        assert_eq!(project.services[0].state, "running");
+        assert_eq!(project.services[1].container_name, "robot-db");
+        assert_eq!(project.services[1].state, "stopped");
+
+        resolve_services(&mut project, &[]);
+        assert_eq!(project.services[1].state, "not running");
+    }
+
+    #[test]
+    fn test_default_compose_files() {
+        let dir = std::env::temp_dir().join(format!("ck-compose-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(default_compose_files(&dir).is_empty());
+
+        for file in [
+            "docker-compose.yml",
+            "docker-compose.override.yml",
+            "compose.override.yaml",
+        ] {
+            fs::write(dir.join(file), "services: {}\n").unwrap();
+        }
+        assert_eq!(
+            default_compose_files(&dir),
+            vec!["docker-compose.yml", "docker-compose.override.yml"]
+        );
+
+        // Only the first base file counts, and only its own override
+        fs::write(dir.join("compose.yaml"), "services: {}\n").unwrap();
+        assert_eq!(
+            default_compose_files(&dir),
+            vec!["compose.yaml", "compose.override.yaml"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,8 +1,17 @@
-//! Container collector - detects running Docker/Podman containers
+//! Container collector - detects Docker/Podman containers across configured runtimes
 
+use super::compose::{compose_container_names, resolve_services};
+use super::devcontainer::{devcontainer_hint, read_devcontainer};
 use super::traits::Collector;
-use crate::config::Config;
-use crate::context::{ContainerInfo, Context};
+use crate::config::{Config, RuntimeConfig};
+use crate::context::{ContainerInfo, ContainerMount, Context};
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long a runtime may take to answer before it is reported unreachable
+const RUNTIME_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Container collector
 #[derive(Debug, Default)]
@@ -19,38 +28,490 @@ impl Collector for ContainerCollector {
     }
 
     fn collect(&self, config: &Config, ctx: &mut Context) {
-        ctx.containers = collect_containers(config);
+        ctx.expected_containers = config
+            .containers
+            .as_ref()
+            .and_then(|c| c.expected.clone())
+            .unwrap_or_default();
+
+        // Containers referenced by targets or configured, whether running or not
+        let mut referenced: Vec<String> = ctx.expected_containers.clone();
+        for target in &ctx.targets {
+            if !target.container_name.is_empty() && !referenced.contains(&target.container_name) {
+                referenced.push(target.container_name.clone());
+            }
+        }
+        if let Some(compose) = &ctx.compose {
+            for name in compose_container_names(compose) {
+                if !referenced.contains(&name) {
+                    referenced.push(name);
+                }
+            }
+        }
+
+        let (containers, unreachable) = collect_containers(config, &referenced);
+        ctx.containers = containers;
+        ctx.unreachable_runtimes = unreachable;
+        if let Some(compose) = ctx.compose.as_mut() {
+            resolve_services(compose, &ctx.containers);
+        }
+
+        if let Some(devcontainer) = read_devcontainer(Path::new("."), &ctx.containers) {
+            if let Some(hint) = devcontainer_hint(&devcontainer) {
+                if !ctx.hints.is_empty() {
+                    ctx.hints.push('\n');
+                }
+                ctx.hints.push_str(&hint);
+            }
+            ctx.devcontainer = Some(devcontainer);
+        }
     }
 }
 
-/// Collect running containers
-fn collect_containers(config: &Config) -> Vec<ContainerInfo> {
+/// Collect containers from every configured runtime in parallel
+///
+/// Also returns the runtimes that could not be queried, with the reason.
+fn collect_containers(
+    config: &Config,
+    referenced: &[String],
+) -> (Vec<ContainerInfo>, Vec<(String, String)>) {
+    let runtimes = configured_runtimes(config);
+
+    let per_runtime: Vec<(String, Result<Vec<ContainerInfo>, String>)> =
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = runtimes
+                .iter()
+                .map(|runtime| scope.spawn(move || collect_runtime(runtime, referenced)))
+                .collect();
+            handles
+                .into_iter()
+                .zip(&runtimes)
+                .map(|(h, runtime)| {
+                    let label = runtime_command(runtime).join(" ");
+                    let result = h
+                        .join()
+                        .unwrap_or_else(|_| Err("query panicked".to_string()));
+                    (label, result)
+                })
+                .collect()
+        });
+
     let mut containers = Vec::new();
+    let mut unreachable = Vec::new();
+    for (label, result) in per_runtime {
+        match result {
+            Ok(found) => containers.extend(found),
+            Err(reason) => unreachable.push((label, reason)),
+        }
+    }
+
+    // A name running on one engine may also exist stopped on another; the running one wins
+    let running: Vec<String> = containers
+        .iter()
+        .filter(|c| c.running)
+        .map(|c| c.name.clone())
+        .collect();
+    containers.retain(|c| c.running || !running.contains(&c.name));
+
+    (containers, unreachable)
+}
+
+/// Command prefix of the first configured runtime, e.g. "podman --connection build-vm"
+pub fn default_runtime(config: &Config) -> String {
+    configured_runtimes(config)
+        .first()
+        .map(|runtime| runtime_command(runtime).join(" "))
+        .unwrap_or_else(|| "podman".to_string())
+}
+
+/// `[[containers.runtimes]]`, or the single `runtime` (default podman)
+fn configured_runtimes(config: &Config) -> Vec<RuntimeConfig> {
+    let containers = config.containers.as_ref();
+    match containers.and_then(|c| c.runtimes.clone()) {
+        Some(runtimes) if !runtimes.is_empty() => runtimes,
+        _ => vec![RuntimeConfig {
+            runtime: containers.and_then(|c| c.runtime.clone()),
+            ..Default::default()
+        }],
+    }
+}
+
+/// Runtime binary followed by its connection arguments
+fn runtime_command(runtime: &RuntimeConfig) -> Vec<String> {
+    let binary = runtime.runtime.as_deref().unwrap_or("podman");
+    let mut command = vec![binary.to_string()];
+
+    if let Some(connection) = &runtime.connection {
+        command.extend(["--connection".to_string(), connection.clone()]);
+    }
+    if let Some(context) = &runtime.context {
+        command.extend(["--context".to_string(), context.clone()]);
+    }
+    if let Some(host) = &runtime.host {
+        let flag = if binary.ends_with("docker") {
+            "--host"
+        } else {
+            "--url"
+        };
+        command.extend([flag.to_string(), host.clone()]);
+    }
+
+    command
+}
+
+/// Running containers of one runtime plus stopped ones among `referenced`
+///
+/// Fails with a short reason when the runtime is missing, errors or times out.
+fn collect_runtime(
+    runtime: &RuntimeConfig,
+    referenced: &[String],
+) -> Result<Vec<ContainerInfo>, String> {
+    let command = runtime_command(runtime);
+    // Shown per container and usable as a command prefix, e.g. "podman --connection build-vm"
+    let label = command.join(" ");
+    let mut containers = Vec::new();
+
+    let output = run_runtime(&command, &["ps", "--format", "{{.Names}}\\t{{.Status}}"])?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr
+            .lines()
+            .next()
+            .unwrap_or("ps failed")
+            .trim()
+            .to_string());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() >= 2 {
+            containers.push(ContainerInfo {
+                name: parts[0].to_string(),
+                status: parts[1].to_string(),
+                runtime: label.clone(),
+                running: true,
+                ..Default::default()
+            });
+        }
+    }
+
+    inspect_containers(&command, &mut containers);
+    containers.extend(stopped_containers(&command, &containers, referenced));
+    Ok(containers)
+}
+
+/// Run `<runtime> <args>`, killing it if it does not finish within `RUNTIME_TIMEOUT`
+///
+/// Remote connections hang rather than fail when the engine is unreachable.
+fn run_runtime(command: &[String], args: &[&str]) -> Result<Output, String> {
+    run_with_timeout(command, args, RUNTIME_TIMEOUT)
+}
+
+fn run_with_timeout(command: &[String], args: &[&str], timeout: Duration) -> Result<Output, String> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => "not installed".to_string(),
+            _ => e.to_string(),
+        })?;
+
+    // Drain the pipes while waiting so a large `inspect` cannot block the child
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let readers = std::thread::scope(|scope| {
+        let out = scope.spawn(|| read_all(stdout.as_mut()));
+        let err = scope.spawn(|| read_all(stderr.as_mut()));
 
-    let runtime = config
-        .containers
-        .as_ref()
-        .and_then(|c| c.runtime.as_deref())
-        .unwrap_or("podman");
-
-    if let Ok(output) = std::process::Command::new(runtime)
-        .args(["ps", "--format", "{{.Names}}\\t{{.Status}}"])
-        .output()
-    {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                let parts: Vec<&str> = line.split('\t').collect();
-                if parts.len() >= 2 {
-                    containers.push(ContainerInfo {
-                        name: parts[0].to_string(),
-                        status: parts[1].to_string(),
-                        runtime: runtime.to_string(),
-                    });
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(20))
                 }
+                Ok(None) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    break Err(format!("timed out after {}s", timeout.as_secs()));
+                }
+                Err(e) => break Err(e.to_string()),
             }
+        };
+        (
+            status,
+            out.join().unwrap_or_default(),
+            err.join().unwrap_or_default(),
+        )
+    });
+
+    let (status, stdout, stderr) = readers;
+    Ok(Output {
+        status: status?,
+        stdout,
+        stderr,
+    })
+}
+
+fn read_all(pipe: Option<&mut impl Read>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+    }
+    buf
+}
+
+/// Referenced containers that exist but are not running
+fn stopped_containers(
+    command: &[String],
+    running: &[ContainerInfo],
+    referenced: &[String],
+) -> Vec<ContainerInfo> {
+    let missing: Vec<&String> = referenced
+        .iter()
+        .filter(|name| !running.iter().any(|c| &c.name == *name))
+        .collect();
+    if missing.is_empty() {
+        return Vec::new();
+    }
+
+    // `inspect` fails for names that do not exist but still prints the others
+    let mut args = vec!["inspect"];
+    args.extend(missing.iter().map(|name| name.as_str()));
+    let output = match run_runtime(command, &args) {
+        Ok(output) => output,
+        Err(_) => return Vec::new(),
+    };
+
+    parse_inspect(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|c| !c.running)
+        .map(|mut c| {
+            c.runtime = command.join(" ");
+            c.status = match c.exit_code {
+                Some(code) => format!("{} ({})", c.status, code),
+                None => c.status,
+            };
+            c
+        })
+        .collect()
+}
+
+/// Fill in image, mounts, working dir and labels from `<runtime> inspect`
+fn inspect_containers(command: &[String], containers: &mut [ContainerInfo]) {
+    if containers.is_empty() {
+        return;
+    }
+
+    let mut args = vec!["inspect"];
+    args.extend(containers.iter().map(|c| c.name.as_str()));
+    let output = match run_runtime(command, &args) {
+        Ok(output) => output,
+        Err(_) => return,
+    };
+    // A container may disappear between `ps` and `inspect`; the rest is still printed
+    let details = parse_inspect(&String::from_utf8_lossy(&output.stdout));
+
+    for container in containers.iter_mut() {
+        if let Some(detail) = details.iter().find(|d| d.name == container.name) {
+            let status = std::mem::take(&mut container.status);
+            let runtime = std::mem::take(&mut container.runtime);
+            *container = ContainerInfo {
+                status,
+                runtime,
+                ..detail.clone()
+            };
         }
     }
+}
+
+/// Parse the JSON array printed by `docker inspect` / `podman inspect`
+fn parse_inspect(json: &str) -> Vec<ContainerInfo> {
+    let entries: Vec<serde_json::Value> = match serde_json::from_str(json) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let text = |value: Option<&serde_json::Value>| {
+                value
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let config = entry.get("Config");
 
-    containers
+            // Docker prefixes names with "/"
+            let name = text(entry.get("Name")).trim_start_matches('/').to_string();
+            if name.is_empty() {
+                return None;
+            }
+
+            // Docker's top-level "Image" is the image ID; the name is in Config.Image
+            let image = [
+                text(config.and_then(|c| c.get("Image"))),
+                text(entry.get("ImageName")),
+                text(entry.get("Image")),
+            ]
+            .into_iter()
+            .find(|i| !i.is_empty())
+            .unwrap_or_default();
+
+            let mounts = entry
+                .get("Mounts")
+                .and_then(|m| m.as_array())
+                .map(|mounts| {
+                    mounts
+                        .iter()
+                        .map(|m| ContainerMount {
+                            kind: text(m.get("Type")),
+                            source: text(m.get("Source")),
+                            destination: text(m.get("Destination")),
+                            read_only: m.get("RW").and_then(|rw| rw.as_bool()) == Some(false),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let labels = config
+                .and_then(|c| c.get("Labels"))
+                .and_then(|l| l.as_object())
+                .map(|labels| {
+                    labels
+                        .iter()
+                        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let state = entry.get("State");
+            let running = state
+                .and_then(|s| s.get("Running"))
+                .and_then(|r| r.as_bool())
+                .unwrap_or(false);
+            // "Created" containers have never run, so their exit code means nothing
+            let status = text(state.and_then(|s| s.get("Status")));
+            let exited = !running && status != "created" && status != "configured";
+
+            Some(ContainerInfo {
+                name,
+                status,
+                running,
+                exit_code: state
+                    .and_then(|s| s.get("ExitCode"))
+                    .and_then(|c| c.as_i64())
+                    .filter(|_| exited)
+                    .map(|c| c as i32),
+                finished_at: state
+                    .filter(|_| exited)
+                    .map(|s| text(s.get("FinishedAt")))
+                    .unwrap_or_default(),
+                image,
+                created: text(entry.get("Created")),
+                working_dir: text(config.and_then(|c| c.get("WorkingDir"))),
+                mounts,
+                labels,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_command() {
+        let remote = RuntimeConfig {
+            connection: Some("build-vm".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            runtime_command(&remote),
+            vec!["podman", "--connection", "build-vm"]
+        );
+
+        let docker = RuntimeConfig {
+            runtime: Some("docker".to_string()),
+            host: Some("ssh://ci@builder".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            runtime_command(&docker),
+            vec!["docker", "--host", "ssh://ci@builder"]
+        );
+    }
+
+    #[test]
+    fn test_run_with_timeout() {
+        let sleep = vec!["sleep".to_string()];
+        let started = Instant::now();
+        let result = run_with_timeout(&sleep, &["10"], Duration::from_millis(200));
+        assert_eq!(result.unwrap_err(), "timed out after 0s");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let missing = vec!["no-such-container-runtime".to_string()];
+        assert_eq!(
+            run_runtime(&missing, &["ps"]).unwrap_err(),
+            "not installed"
+        );
+    }
+
+    #[test]
+    fn test_parse_docker_inspect() {
+        let json = r#"[{
+  "Id": "4f1c",
+  "Created": "2024-05-01T09:12:33.5Z",
+  "Name": "/aosp-build",
+  "Image": "sha256:9a2b",
+  "State": {"Status": "running", "Running": true, "ExitCode": 0},
+  "Mounts": [
+    {"Type": "bind", "Source": "/home/dev/aosp", "Destination": "/src", "RW": true},
+    {"Type": "volume", "Name": "ccache", "Source": "/var/lib/docker/volumes/ccache/_data", "Destination": "/ccache", "RW": false}
+  ],
+  "Config": {
+    "Image": "aosp-builder:14",
+    "WorkingDir": "/src",
+    "Labels": {"maintainer": "platform-team"}
+  }
+}]"#;
+
+        let containers = parse_inspect(json);
+        assert_eq!(containers.len(), 1);
+        let c = &containers[0];
+        assert_eq!(c.name, "aosp-build");
+        assert_eq!(c.image, "aosp-builder:14");
+        assert!(c.running);
+        assert_eq!(c.working_dir, "/src");
+        assert_eq!(c.mounts.len(), 2);
+        assert_eq!(c.mounts[0].destination, "/src");
+        assert!(c.mounts[1].read_only);
+        assert_eq!(
+            c.labels.get("maintainer").map(String::as_str),
+            Some("platform-team")
+        );
+        assert_eq!(c.exit_code, None);
+    }
+
+    #[test]
+    fn test_parse_stopped_podman_inspect() {
+        let json = r#"[{
+  "Name": "yocto-build",
+  "ImageName": "localhost/yocto:scarthgap",
+  "State": {"Status": "exited", "Running": false, "ExitCode": 137, "FinishedAt": "2024-05-02T18:40:01Z"},
+  "Config": {"Image": "localhost/yocto:scarthgap", "WorkingDir": "/work"}
+}]"#;
+
+        let containers = parse_inspect(json);
+        assert!(!containers[0].running);
+        assert_eq!(containers[0].exit_code, Some(137));
+        assert_eq!(containers[0].finished_at, "2024-05-02T18:40:01Z");
+    }
 }
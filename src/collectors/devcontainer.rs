@@ -0,0 +1,284 @@
+//! Dev Container parsing - devcontainer.json and the container created from it
+
+use crate::context::{ContainerInfo, DevContainer};
+use std::fs;
+use std::path::Path;
+
+/// Locations checked for a Dev Container definition, in order
+const DEVCONTAINER_FILES: &[&str] = &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+/// Read the project's devcontainer.json and match it to a container
+pub fn read_devcontainer(root: &Path, containers: &[ContainerInfo]) -> Option<DevContainer> {
+    let config_file = DEVCONTAINER_FILES
+        .iter()
+        .map(|f| f.to_string())
+        .chain(named_configs(root))
+        .find(|f| root.join(f).is_file())?;
+    let content = fs::read_to_string(root.join(&config_file)).ok()?;
+
+    let local_folder = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let mut devcontainer = parse_devcontainer(&content, &local_folder)?;
+    devcontainer.config_file = config_file;
+
+    // The devcontainer CLI and VS Code label containers with the local folder they serve
+    let folder = local_folder.display().to_string();
+    if let Some(container) = containers
+        .iter()
+        .find(|c| c.running && c.labels.get("devcontainer.local_folder") == Some(&folder))
+    {
+        devcontainer.container = container.name.clone();
+        devcontainer.runtime = container.runtime.clone();
+    }
+
+    Some(devcontainer)
+}
+
+/// "builds run in container X as user Y at path Z", when the container is running
+pub fn devcontainer_hint(devcontainer: &DevContainer) -> Option<String> {
+    if devcontainer.container.is_empty() {
+        return None;
+    }
+
+    // No -it: the agent's shell has no TTY, and slim images may lack bash
+    let mut exec = format!("{} exec", devcontainer.runtime);
+    let mut hint = format!("Builds run in container {}", devcontainer.container);
+    if !devcontainer.remote_user.is_empty() {
+        hint.push_str(&format!(" as user {}", devcontainer.remote_user));
+        exec.push_str(&format!(" -u {}", devcontainer.remote_user));
+    }
+    hint.push_str(&format!(" at {}", devcontainer.workspace_folder));
+    exec.push_str(&format!(
+        " -w {} {} sh -c '<command>'",
+        devcontainer.workspace_folder, devcontainer.container
+    ));
+
+    Some(format!(
+        "{} (from {}): `{}`",
+        hint, devcontainer.config_file, exec
+    ))
+}
+
+/// `.devcontainer/<name>/devcontainer.json` for repos with several definitions
+fn named_configs(root: &Path) -> Vec<String> {
+    let mut configs: Vec<String> = fs::read_dir(root.join(".devcontainer"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().join("devcontainer.json").is_file())
+                .map(|e| {
+                    format!(
+                        ".devcontainer/{}/devcontainer.json",
+                        e.file_name().to_string_lossy()
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    configs.sort();
+    configs
+}
+
+/// Extract image, user, workspace folder, post-create command and mounts
+fn parse_devcontainer(content: &str, local_folder: &Path) -> Option<DevContainer> {
+    let json: serde_json::Value = serde_json::from_str(&strip_jsonc(content)).ok()?;
+    let basename = local_folder
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let expand = |value: &str| {
+        value
+            .replace("${localWorkspaceFolderBasename}", &basename)
+            .replace(
+                "${localWorkspaceFolder}",
+                &local_folder.display().to_string(),
+            )
+            .replace("${containerWorkspaceFolderBasename}", &basename)
+    };
+    let text = |value: Option<&serde_json::Value>| {
+        value
+            .and_then(|v| v.as_str())
+            .map(expand)
+            .unwrap_or_default()
+    };
+
+    let build = json.get("build");
+    let dockerfile = [
+        text(build.and_then(|b| b.get("dockerfile"))),
+        text(json.get("dockerFile")),
+    ]
+    .into_iter()
+    .find(|d| !d.is_empty())
+    .unwrap_or_default();
+
+    let remote_user = [
+        text(json.get("remoteUser")),
+        text(json.get("containerUser")),
+    ]
+    .into_iter()
+    .find(|u| !u.is_empty())
+    .unwrap_or_default();
+
+    // Default workspace mount used by the devcontainer CLI
+    let workspace_folder = Some(text(json.get("workspaceFolder")))
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| format!("/workspaces/{}", basename));
+
+    // A string, an argv array, or an object of named parallel commands
+    let post_create_command = match json.get("postCreateCommand") {
+        Some(serde_json::Value::String(cmd)) => expand(cmd),
+        Some(serde_json::Value::Array(argv)) => argv
+            .iter()
+            .filter_map(|a| a.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(serde_json::Value::Object(commands)) => commands
+            .values()
+            .filter_map(|c| match c {
+                serde_json::Value::String(cmd) => Some(expand(cmd)),
+                serde_json::Value::Array(argv) => Some(
+                    argv.iter()
+                        .filter_map(|a| a.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" & "),
+        _ => String::new(),
+    };
+
+    // "source=x,target=y,type=bind" strings or {source, target, type} objects
+    let mounts = json
+        .get("mounts")
+        .and_then(|m| m.as_array())
+        .map(|mounts| {
+            mounts
+                .iter()
+                .filter_map(|mount| {
+                    let field = |key: &str| match mount {
+                        serde_json::Value::String(spec) => spec
+                            .split(',')
+                            .filter_map(|part| part.split_once('='))
+                            .find(|(k, _)| *k == key || (key == "target" && *k == "dst"))
+                            .map(|(_, v)| expand(v))
+                            .unwrap_or_default(),
+                        _ => text(mount.get(key)),
+                    };
+                    let (source, target) = (field("source"), field("target"));
+                    (!target.is_empty()).then(|| format!("{} -> {}", source, target))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(DevContainer {
+        image: text(json.get("image")),
+        dockerfile,
+        remote_user,
+        workspace_folder,
+        post_create_command,
+        mounts,
+        ..Default::default()
+    })
+}
+
+/// Drop `//` and `/* */` comments and trailing commas so serde_json accepts JSONC
+fn strip_jsonc(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|&n| n != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = ' ';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+            }
+            (']' | '}', _) => {
+                // Remove a trailing comma before the closing bracket
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.truncate(trimmed - 1);
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_devcontainer_jsonc() {
+        let content = r#"{
+  // Build image for the firmware
+  "name": "fw",
+  "build": { "dockerfile": "Dockerfile", "context": ".." },
+  "remoteUser": "vscode",
+  /* mounted next to the sources */
+  "mounts": [
+    "source=${localWorkspaceFolder}/../toolchains,target=/opt/toolchains,type=bind",
+    { "source": "ccache", "target": "/home/vscode/.ccache", "type": "volume" },
+  ],
+  "postCreateCommand": ["west", "update"],
+  "customizations": { "vscode": { "settings": { "url": "http://example.com//x" } } },
+}"#;
+
+        let dc = parse_devcontainer(content, Path::new("/home/dev/firmware")).unwrap();
+        assert_eq!(dc.dockerfile, "Dockerfile");
+        assert_eq!(dc.remote_user, "vscode");
+        assert_eq!(dc.workspace_folder, "/workspaces/firmware");
+        assert_eq!(dc.post_create_command, "west update");
+        assert_eq!(
+            dc.mounts,
+            vec![
+                "/home/dev/firmware/../toolchains -> /opt/toolchains",
+                "ccache -> /home/vscode/.ccache",
+            ]
+        );
+
+        let running = DevContainer {
+            config_file: ".devcontainer/devcontainer.json".to_string(),
+            container: "fw_devcontainer".to_string(),
+            runtime: "docker".to_string(),
+            ..dc
+        };
+        assert_eq!(
+            devcontainer_hint(&running).unwrap(),
+            "Builds run in container fw_devcontainer as user vscode at /workspaces/firmware \
+             (from .devcontainer/devcontainer.json): \
+             `docker exec -u vscode -w /workspaces/firmware fw_devcontainer sh -c '<command>'`"
+        );
+    }
+}
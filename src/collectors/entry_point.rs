@@ -0,0 +1,453 @@
+//! Entry point parsing - commands offered by a build wrapper script
+//!
+//! Reads `case "$1" in ... )` dispatch blocks, `usage()` help text and the
+//! functions defined in the script to list subcommands with descriptions and
+//! argument hints.
+
+use super::format_command;
+use regex::Regex;
+use std::fs;
+
+/// Upper bound on commands listed from an entry point
+const MAX_COMMANDS: usize = 20;
+
+/// Functions that print help rather than being commands themselves
+const HELP_FUNCTIONS: &[&str] = &["usage", "help", "show_help", "print_usage", "print_help"];
+
+/// Common helper functions that are never subcommands
+const HELPER_FUNCTIONS: &[&str] = &[
+    "main", "die", "fail", "error", "err", "warn", "info", "log", "debug", "msg", "fatal",
+];
+
+/// A subcommand offered by the entry point
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ScriptCommand {
+    name: String,
+    args: Vec<String>,
+    description: String,
+}
+
+/// A shell function with its leading comment and body
+struct ScriptFunction {
+    name: String,
+    description: String,
+    body: Vec<String>,
+}
+
+/// Parse entry point script to extract available commands
+///
+/// Each command is rendered as `<script> <command> <args>  # description`.
+pub fn parse_entry_point_commands(entry_point: &str) -> Vec<String> {
+    let content = match fs::read_to_string(entry_point) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+
+    parse_script_commands(&content)
+        .into_iter()
+        .take(MAX_COMMANDS)
+        .map(|cmd| {
+            let mut line = format!("{} {}", entry_point, cmd.name);
+            for arg in &cmd.args {
+                line.push(' ');
+                line.push_str(arg);
+            }
+            format_command(line, &cmd.description)
+        })
+        .collect()
+}
+
+fn parse_script_commands(content: &str) -> Vec<ScriptCommand> {
+    let lines: Vec<&str> = content.lines().collect();
+    let functions = parse_functions(&lines);
+
+    let mut commands = parse_case_dispatch(&lines, &functions);
+
+    let usage = functions
+        .iter()
+        .find(|f| HELP_FUNCTIONS.contains(&f.name.as_str()))
+        .map(|f| parse_usage(&f.body))
+        .unwrap_or_default();
+    for entry in usage {
+        match commands.iter_mut().find(|c| c.name == entry.name) {
+            Some(existing) => {
+                // Help text is written for humans, so prefer it over derived hints
+                if !entry.description.is_empty() {
+                    existing.description = entry.description;
+                }
+                if !entry.args.is_empty() {
+                    existing.args = entry.args;
+                }
+            }
+            None => commands.push(entry),
+        }
+    }
+
+    // Scripts that dispatch with `"$@"` expose their functions directly
+    if commands.is_empty() {
+        commands = functions
+            .iter()
+            .filter(|f| {
+                !f.name.starts_with('_')
+                    && !HELP_FUNCTIONS.contains(&f.name.as_str())
+                    && !HELPER_FUNCTIONS.contains(&f.name.as_str())
+            })
+            .map(|f| ScriptCommand {
+                name: f.name.clone(),
+                args: extract_args(&f.body, 1),
+                description: f.description.clone(),
+            })
+            .collect();
+    }
+
+    commands
+}
+
+/// Comment lines directly above `index`, joined into one sentence
+fn leading_comment(lines: &[&str], index: usize) -> String {
+    let mut comments = Vec::new();
+    for line in lines[..index].iter().rev() {
+        let line = line.trim();
+        match line.strip_prefix('#') {
+            Some(text) if !text.starts_with('!') => comments.push(text.trim()),
+            _ => break,
+        }
+    }
+    comments.reverse();
+    comments.retain(|c| !c.is_empty() && !c.chars().all(|ch| ch == '#' || ch == '-' || ch == '='));
+    comments.join(" ")
+}
+
+fn parse_functions(lines: &[&str]) -> Vec<ScriptFunction> {
+    let header =
+        Regex::new(r"^(\s*)(?:function\s+([A-Za-z_][\w:-]*)\s*(?:\(\))?|([A-Za-z_][\w:-]*)\s*\(\))\s*\{?\s*(?:#.*)?$")
+            .expect("regex");
+    let mut functions = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let caps = match header.captures(line) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        let indent = caps[1].len();
+        let name = caps.get(2).or_else(|| caps.get(3)).expect("name").as_str();
+
+        // The body ends at the first closing brace indented no deeper than the header
+        let body: Vec<String> = lines[i + 1..]
+            .iter()
+            .take_while(|l| {
+                let trimmed = l.trim_start();
+                !(trimmed.starts_with('}') && l.len() - trimmed.len() <= indent)
+            })
+            .map(|l| l.to_string())
+            .collect();
+
+        functions.push(ScriptFunction {
+            name: name.to_string(),
+            description: leading_comment(lines, i),
+            body,
+        });
+    }
+
+    functions
+}
+
+/// Commands from the `case` statement that dispatches on the first argument
+fn parse_case_dispatch(lines: &[&str], functions: &[ScriptFunction]) -> Vec<ScriptCommand> {
+    let assigned = Regex::new(r#"\b(\w+)=["']?\$\{?1\b"#).expect("regex");
+    let case_start = Regex::new(r#"^\s*case\s+"?\$\{?(\w+)[^\s"]*"?\s+in\b"#).expect("regex");
+    let pattern = Regex::new(r#"^\s*\(?\s*([\w"'.|*?-][\w"'.|*? -]*)\)\s*(.*)$"#).expect("regex");
+
+    // Variables holding the first argument (`cmd=$1`, `COMMAND="${1:-help}"`)
+    let mut dispatch_vars = vec!["1".to_string()];
+    for line in lines {
+        for caps in assigned.captures_iter(line) {
+            dispatch_vars.push(caps[1].to_string());
+        }
+    }
+
+    let start = match lines.iter().position(|l| {
+        case_start
+            .captures(l)
+            .is_some_and(|c| dispatch_vars.contains(&c[1].to_string()))
+    }) {
+        Some(start) => start,
+        None => return Vec::new(),
+    };
+
+    let mut commands: Vec<ScriptCommand> = Vec::new();
+    let mut depth = 1;
+    let mut expect_pattern = true;
+    let mut current: Option<(ScriptCommand, Vec<String>)> = None;
+
+    let finish = |current: &mut Option<(ScriptCommand, Vec<String>)>,
+                  out: &mut Vec<ScriptCommand>| {
+        if let Some((mut cmd, body)) = current.take() {
+            if cmd.args.is_empty() {
+                cmd.args = extract_args(&body, 2);
+            }
+            // A branch that just calls a function inherits its description and arguments
+            let called = body.iter().find_map(|l| {
+                let word = l.split_whitespace().next()?;
+                functions.iter().find(|f| f.name == word)
+            });
+            if let Some(function) = called {
+                if cmd.description.is_empty() {
+                    cmd.description = function.description.clone();
+                }
+                if cmd.args.is_empty() {
+                    cmd.args = extract_args(&function.body, 1);
+                }
+            }
+            out.push(cmd);
+        }
+    };
+
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let trimmed = line.trim();
+        if trimmed.starts_with("case ") {
+            depth += 1;
+        }
+        if trimmed == "esac" || trimmed.starts_with("esac ") || trimmed.starts_with("esac;") {
+            depth -= 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        if depth > 1 || trimmed.starts_with('#') || trimmed.is_empty() {
+            if let Some((_, body)) = current.as_mut() {
+                body.push(line.to_string());
+            }
+            continue;
+        }
+
+        let mut rest = trimmed.to_string();
+        if expect_pattern {
+            if let Some(caps) = pattern.captures(trimmed) {
+                finish(&mut current, &mut commands);
+
+                let names: Vec<String> = caps[1]
+                    .split('|')
+                    .map(|n| n.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+                    .filter(|n| !n.is_empty() && !n.starts_with('-') && !n.contains(['*', '?']))
+                    .collect();
+                rest = caps[2].to_string();
+
+                current = names.first().map(|name| {
+                    let inline = rest
+                        .split_once('#')
+                        .map(|(_, c)| c.trim().to_string())
+                        .unwrap_or_default();
+                    let description = if inline.is_empty() {
+                        leading_comment(lines, i)
+                    } else {
+                        inline
+                    };
+                    let command = ScriptCommand {
+                        name: name.clone(),
+                        description,
+                        ..Default::default()
+                    };
+                    (command, Vec::new())
+                });
+                expect_pattern = false;
+            }
+        }
+
+        if let Some((_, body)) = current.as_mut() {
+            body.push(rest.clone());
+        }
+        if rest.ends_with(";;") || rest.ends_with(";&") || rest.ends_with(";;&") {
+            expect_pattern = true;
+        }
+    }
+    finish(&mut current, &mut commands);
+
+    commands
+}
+
+/// Argument hints from positional parameters used in a body
+///
+/// `target=$2` becomes `<target>`, `jobs=${3:-8}` becomes `[jobs]` and a bare
+/// `$2` becomes `<arg1>`; `first` is the position of the first argument.
+fn extract_args(body: &[String], first: usize) -> Vec<String> {
+    let named = Regex::new(r#"\b(\w+)=["']?\$\{?(\d)(:?[-=])?"#).expect("regex");
+    let bare = Regex::new(r"\$\{?(\d)\b").expect("regex");
+    let mut args: Vec<(usize, String)> = Vec::new();
+
+    for line in body {
+        for caps in named.captures_iter(line) {
+            let pos: usize = caps[2].parse().unwrap_or(0);
+            if pos >= first && !args.iter().any(|(p, _)| *p == pos) {
+                let hint = if caps.get(3).is_some() {
+                    format!("[{}]", caps[1].to_lowercase())
+                } else {
+                    format!("<{}>", caps[1].to_lowercase())
+                };
+                args.push((pos, hint));
+            }
+        }
+    }
+    for line in body {
+        for caps in bare.captures_iter(line) {
+            let pos: usize = caps[1].parse().unwrap_or(0);
+            if pos >= first && !args.iter().any(|(p, _)| *p == pos) {
+                args.push((pos, format!("<arg{}>", pos + 1 - first)));
+            }
+        }
+    }
+
+    args.sort();
+    args.into_iter().map(|(_, hint)| hint).collect()
+}
+
+/// Command lines from help text, e.g. `  build <target> [jobs]   Build a target`
+fn parse_usage(body: &[String]) -> Vec<ScriptCommand> {
+    let entry = Regex::new(
+        r#"^\s+(?:\S*\.sh\s+|\$0\s+|\$\{0##\*/\}\s+|\$\(basename[^)]*\)\s+)?([a-z][\w-]*)((?:\s+(?:<[^>]+>|\[[^\]]+\])(?:\.\.\.)?)*)(?:\s{2,}|\s+-\s+|\s*:\s+)(\S.*)$"#,
+    )
+    .expect("regex");
+    let mut commands: Vec<ScriptCommand> = Vec::new();
+
+    for line in body {
+        // Unwrap `echo "..."` / `printf '...\n'` lines; heredoc lines are used as-is
+        let trimmed = line.trim_start();
+        let text = ["echo ", "printf "]
+            .iter()
+            .find_map(|cmd| trimmed.strip_prefix(cmd))
+            .map(|rest| {
+                rest.trim()
+                    .trim_start_matches("-e ")
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .replace("\\n", "")
+                    .replace("\\t", "    ")
+            })
+            .unwrap_or_else(|| line.to_string());
+
+        if let Some(caps) = entry.captures(&text) {
+            let name = caps[1].to_string();
+            if commands.iter().any(|c| c.name == name) {
+                continue;
+            }
+            let args = caps[2].split_whitespace().map(str::to_string).collect();
+            commands.push(ScriptCommand {
+                name,
+                args,
+                description: caps[3]
+                    .trim_start_matches(['-', ':', ' '])
+                    .trim()
+                    .to_string(),
+            });
+        }
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(content: &str) -> Vec<String> {
+        parse_script_commands(content)
+            .into_iter()
+            .map(|c| format!("{} {} | {}", c.name, c.args.join(" "), c.description))
+            .collect()
+    }
+
+    #[test]
+    fn test_case_dispatch_with_functions_and_usage() {
+        let script = r#"#!/bin/bash
+set -euo pipefail
+
+usage() {
+    cat <<EOF
+Usage: $0 <command> [args]
+
+Commands:
+    build <target> [jobs]   Build a target inside the container
+    shell                   Open a shell in the build container
+EOF
+}
+
+# Flash the built image to a connected device
+do_flash() {
+    local serial=$1
+    fastboot -s "$serial" flashall
+}
+
+cmd="${1:-help}"
+case "$cmd" in
+    build|b)
+        target=$2
+        jobs=${3:-16}
+        podman exec aosp-build m -j"$jobs"
+        ;;
+    flash) do_flash "${@:2}" ;;
+    # Remove build outputs
+    clean)
+        rm -rf out
+        ;;
+    -h|--help|help) usage ;;
+    *) usage; exit 1 ;;
+esac
+"#;
+
+        assert_eq!(
+            render(script),
+            vec![
+                "build <target> [jobs] | Build a target inside the container",
+                "flash <serial> | Flash the built image to a connected device",
+                "clean  | Remove build outputs",
+                "help  | ",
+                "shell  | Open a shell in the build container",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_functions_without_dispatch() {
+        let script = r#"#!/bin/bash
+# Build the firmware for a board
+function build {
+    make BOARD="$1"
+}
+
+log() { echo "$@"; }
+
+# Start the emulator
+emulator() {
+    local avd=${1:-pixel}
+}
+
+"$@"
+"#;
+
+        assert_eq!(
+            render(script),
+            vec![
+                "build <board> | Build the firmware for a board",
+                "emulator [avd] | Start the emulator",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_usage_echo_lines() {
+        let body: Vec<String> = [
+            r#"echo "Usage: ./build.sh <command>""#,
+            r#"echo "  sync                 Repo sync all projects""#,
+            r#"echo "  lunch <target>  -  Select a lunch target""#,
+            r#"echo "  -v, --verbose        Verbose output""#,
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let commands = parse_usage(&body);
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].name, "sync");
+        assert_eq!(commands[1].args, vec!["<target>"]);
+        assert_eq!(commands[1].description, "Select a lunch target");
+    }
+}
@@ -0,0 +1,265 @@
+//! Environment derivation - replays command history into the effective shell state
+
+use super::shell_history::is_shell_entry;
+use crate::context::{ActiveEnvironment, HistoryEntry};
+
+/// Variables that are tracked even when set as a command prefix (`MACHINE=x bitbake ...`)
+const PREFIX_VARS: &[&str] = &["MACHINE", "DISTRO", "ROS_DISTRO"];
+
+/// Derive the effective environment of each session from chronological history
+///
+/// Returns one environment per session, most recently active first. Failed
+/// commands are skipped, so a mistyped `lunch` does not count. Imported shell
+/// history is skipped too: without a cwd its `lunch` may belong to another tree.
+pub fn derive_environments(entries: &[HistoryEntry]) -> Vec<ActiveEnvironment> {
+    let mut envs: Vec<(usize, ActiveEnvironment)> = Vec::new();
+
+    for (idx, entry) in entries.iter().enumerate() {
+        if entry.failed() || is_shell_entry(entry) {
+            continue;
+        }
+
+        let pos = match envs
+            .iter()
+            .position(|(_, e)| e.session_id == entry.session_id)
+        {
+            Some(pos) => pos,
+            None => {
+                envs.push((
+                    idx,
+                    ActiveEnvironment {
+                        session_id: entry.session_id.clone(),
+                        ..Default::default()
+                    },
+                ));
+                envs.len() - 1
+            }
+        };
+
+        let (last_idx, env) = &mut envs[pos];
+        if apply_command(env, &entry.command) {
+            *last_idx = idx;
+            env.cwd = entry.cwd.clone();
+            env.updated_at = entry.timestamp.clone();
+        }
+    }
+
+    envs.retain(|(_, env)| !env.is_empty());
+    envs.sort_by_key(|(idx, _)| std::cmp::Reverse(*idx));
+    envs.into_iter().map(|(_, env)| env).collect()
+}
+
+/// Apply one command line to the environment, returning true if anything changed
+fn apply_command(env: &mut ActiveEnvironment, command: &str) -> bool {
+    let mut changed = false;
+
+    for words in split_simple_commands(command) {
+        let mut i = 0;
+        while let Some((name, value)) = words.get(i).and_then(|w| split_assignment(w)) {
+            if PREFIX_VARS.contains(&name) {
+                changed |= set_variable(env, name, value);
+            }
+            i += 1;
+        }
+
+        let args = &words[(i + 1).min(words.len())..];
+        match words.get(i).map(|s| s.as_str()) {
+            Some("lunch") => {
+                // Newer trees also accept `lunch <product> <release> <variant>`
+                if args.len() >= 3 && !args[0].contains('-') {
+                    env.lunch_target = args[..3].join("-");
+                    changed = true;
+                } else if let Some(target) = args.first() {
+                    env.lunch_target = target.clone();
+                    changed = true;
+                }
+            }
+            Some("source") | Some(".") => {
+                if let Some(script) = args.first() {
+                    if let Some(distro) = ros_distro_from_path(script) {
+                        env.ros_distro = distro.to_string();
+                    }
+                    env.sourced.retain(|s| s != script);
+                    env.sourced.push(script.clone());
+                    changed = true;
+                }
+            }
+            Some("export") => {
+                for arg in args {
+                    if let Some((name, value)) = split_assignment(arg) {
+                        changed |= set_variable(env, name, value);
+                    }
+                }
+            }
+            Some("unset") => {
+                let before = env.variables.len();
+                env.variables.retain(|(name, _)| !args.contains(name));
+                changed |= env.variables.len() != before;
+            }
+            _ => {}
+        }
+    }
+
+    changed
+}
+
+fn set_variable(env: &mut ActiveEnvironment, name: &str, value: &str) -> bool {
+    if name == "ROS_DISTRO" {
+        env.ros_distro = value.to_string();
+    }
+    env.variables.retain(|(n, _)| n != name);
+    env.variables.push((name.to_string(), value.to_string()));
+    true
+}
+
+/// Split `NAME=value` into its parts if `word` is a valid shell assignment
+fn split_assignment(word: &str) -> Option<(&str, &str)> {
+    let (name, value) = word.split_once('=')?;
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some((name, value))
+}
+
+/// Extract the distro from a path like `/opt/ros/humble/setup.bash`
+fn ros_distro_from_path(path: &str) -> Option<&str> {
+    let rest = path.split("/opt/ros/").nth(1)?;
+    rest.split('/').next().filter(|d| !d.is_empty())
+}
+
+/// Split a command line into simple commands (word lists), honoring quotes
+///
+/// `;`, `&`, `|`, parentheses and newlines separate commands.
+fn split_simple_commands(command: &str) -> Vec<Vec<String>> {
+    let mut commands = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars();
+    let (mut single, mut double) = (false, false);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if !double => {
+                single = !single;
+                in_word = true;
+            }
+            '"' if !single => {
+                double = !double;
+                in_word = true;
+            }
+            '\\' if !single => {
+                if let Some(next) = chars.next() {
+                    if next != '\n' {
+                        word.push(next);
+                        in_word = true;
+                    }
+                }
+            }
+            _ if single || double => word.push(c),
+            ' ' | '\t' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            ';' | '&' | '|' | '(' | ')' | '\n' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            _ => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(session: &str, command: &str, exit_code: Option<i32>) -> HistoryEntry {
+        HistoryEntry {
+            command: command.to_string(),
+            session_id: session.to_string(),
+            exit_code,
+            count: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_derive_aosp_environment() {
+        let entries = vec![
+            entry(
+                "s1",
+                "source build/envsetup.sh && lunch aosp_x86_64-userdebug",
+                Some(0),
+            ),
+            entry("s1", "lunch typo_target", Some(1)),
+            entry("s1", "export OUT_DIR=out_x86 FOO='a b'", None),
+            entry("s1", "unset FOO; m -j32", Some(0)),
+        ];
+
+        let envs = derive_environments(&entries);
+        assert_eq!(envs.len(), 1);
+        assert_eq!(envs[0].lunch_target, "aosp_x86_64-userdebug");
+        assert_eq!(envs[0].sourced, vec!["build/envsetup.sh"]);
+        assert_eq!(
+            envs[0].variables,
+            vec![("OUT_DIR".to_string(), "out_x86".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_derive_split_lunch_arguments() {
+        let entries = vec![entry(
+            "s1",
+            "lunch aosp_cf_x86_64_phone trunk_staging userdebug",
+            Some(0),
+        )];
+
+        let envs = derive_environments(&entries);
+        assert_eq!(
+            envs[0].lunch_target,
+            "aosp_cf_x86_64_phone-trunk_staging-userdebug"
+        );
+    }
+
+    #[test]
+    fn test_derive_per_session_most_recent_first() {
+        let entries = vec![
+            entry("old", "MACHINE=qemuarm64 bitbake core-image-minimal", None),
+            entry("new", "source /opt/ros/humble/setup.bash", None),
+            entry("new", "colcon build", None),
+            // Imported shell history may come from another tree
+            entry("shell:zsh", "lunch other_tree-eng", None),
+        ];
+
+        let envs = derive_environments(&entries);
+        assert_eq!(envs.len(), 2);
+        assert_eq!(envs[0].session_id, "new");
+        assert_eq!(envs[0].ros_distro, "humble");
+        assert_eq!(envs[1].session_id, "old");
+        assert_eq!(
+            envs[1].variables,
+            vec![("MACHINE".to_string(), "qemuarm64".to_string())]
+        );
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::traits::Collector;
 use crate::config::Config;
-use crate::context::{Context, GitInfo};
+use crate::context::{Context, GitInfo, WorkingFile};
 use std::fs;
 use std::path::Path;
 
@@ -29,16 +29,19 @@ impl Collector for GitCollector {
     }
 }
 
-/// Collect git info from a single repository path
-fn collect_git_info_for_path(repo_path: &str) -> Option<GitInfo> {
-    // Check if this path is a git repository
-    let is_git = std::process::Command::new("git")
-        .args(["-C", repo_path, "rev-parse", "--is-inside-work-tree"])
+/// Check whether a path is inside a git work tree
+fn is_git_work_tree(path: &str) -> bool {
+    std::process::Command::new("git")
+        .args(["-C", path, "rev-parse", "--is-inside-work-tree"])
         .output()
         .map(|o| o.status.success())
-        .unwrap_or(false);
+        .unwrap_or(false)
+}
 
-    if !is_git {
+/// Collect git info from a single repository path
+fn collect_git_info_for_path(repo_path: &str) -> Option<GitInfo> {
+    // Check if this path is a git repository
+    if !is_git_work_tree(repo_path) {
         return None;
     }
 
@@ -108,10 +111,107 @@ fn collect_git_info_for_path(repo_path: &str) -> Option<GitInfo> {
     Some(info)
 }
 
+/// Directories that never contain repositories worth reporting
+const DEFAULT_EXCLUDES: &[&str] = &["node_modules", "target", "out"];
+
+/// A single .gitignore-style exclusion rule
+#[derive(Debug)]
+struct ExcludeRule {
+    pattern: glob::Pattern,
+    /// Pattern contains a slash and is matched against the path relative to the scan root
+    anchored: bool,
+    /// Pattern starts with `!` and re-includes a previously excluded directory
+    negated: bool,
+}
+
+impl ExcludeRule {
+    /// Parse one line of .gitignore syntax (comments and blank lines yield None)
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        // Only directories are scanned, so a trailing slash changes nothing
+        let line = line.trim_end_matches('/');
+        // "**/name" is the same as "name" when no other slash follows
+        let line = match line.strip_prefix("**/") {
+            Some(rest) if !rest.contains('/') => rest,
+            _ => line,
+        };
+
+        let anchored = line.contains('/');
+        let pattern = glob::Pattern::new(line.trim_start_matches('/')).ok()?;
+
+        Some(Self {
+            pattern,
+            anchored,
+            negated,
+        })
+    }
+
+    fn matches(&self, rel_path: &str, name: &str) -> bool {
+        if self.anchored {
+            let options = glob::MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            };
+            self.pattern.matches_with(rel_path, options)
+        } else {
+            self.pattern.matches(name)
+        }
+    }
+}
+
+/// Exclusion rules applied while scanning for repositories
+#[derive(Debug, Default)]
+struct ScanFilter {
+    rules: Vec<ExcludeRule>,
+}
+
+impl ScanFilter {
+    /// Build the filter from built-in defaults, the scan root's .gitignore and config excludes
+    fn new(base_path: &str, extra: &[String]) -> Self {
+        let mut rules: Vec<ExcludeRule> = DEFAULT_EXCLUDES
+            .iter()
+            .filter_map(|p| ExcludeRule::parse(p))
+            .collect();
+
+        if let Ok(content) = fs::read_to_string(Path::new(base_path).join(".gitignore")) {
+            rules.extend(content.lines().filter_map(ExcludeRule::parse));
+        }
+
+        rules.extend(extra.iter().filter_map(|p| ExcludeRule::parse(p)));
+
+        Self { rules }
+    }
+
+    /// Check whether a directory should be skipped (last matching rule wins)
+    fn is_excluded(&self, rel_path: &str, name: &str) -> bool {
+        // Hidden directories (.repo, .cache, ...) are always skipped
+        if name.starts_with('.') {
+            return true;
+        }
+
+        let mut excluded = false;
+        for rule in &self.rules {
+            if rule.matches(rel_path, name) {
+                excluded = !rule.negated;
+            }
+        }
+        excluded
+    }
+}
+
 /// Auto-detect git repositories in subdirectories
-fn find_git_repos(base_path: &str, max_depth: usize) -> Vec<String> {
+fn find_git_repos(base_path: &str, max_depth: usize, filter: &ScanFilter) -> Vec<String> {
     let mut repos = Vec::new();
-    find_git_repos_recursive(base_path, base_path, 0, max_depth, &mut repos);
+    find_git_repos_recursive(base_path, base_path, 0, max_depth, filter, &mut repos);
     repos.sort();
     repos
 }
@@ -121,6 +221,7 @@ fn find_git_repos_recursive(
     current_path: &str,
     depth: usize,
     max_depth: usize,
+    filter: &ScanFilter,
     repos: &mut Vec<String>,
 ) {
     if depth > max_depth {
@@ -148,12 +249,11 @@ fn find_git_repos_recursive(
             let path = entry.path();
             if path.is_dir() {
                 let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                // Skip hidden directories and common non-repo directories
-                if name.starts_with('.')
-                    || name == "node_modules"
-                    || name == "target"
-                    || name == "out"
-                {
+                let rel_path = path
+                    .strip_prefix(base_path)
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if filter.is_excluded(&rel_path, name) {
                     continue;
                 }
                 find_git_repos_recursive(
@@ -161,6 +261,7 @@ fn find_git_repos_recursive(
                     path.to_str().unwrap_or(""),
                     depth + 1,
                     max_depth,
+                    filter,
                     repos,
                 );
             }
@@ -168,19 +269,15 @@ fn find_git_repos_recursive(
     }
 }
 
-/// Collect git info from multiple repositories based on config
-pub fn collect_git_repos(config: &Config) -> Vec<GitInfo> {
-    let mut repos = Vec::new();
-    let cwd = std::env::current_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_else(|_| ".".to_string());
+/// List repository paths (relative to the current directory) according to config
+///
+/// Returns `["."]` when the current directory is itself a repository.
+pub fn discover_repo_paths(config: &Config) -> Vec<String> {
+    let cwd = current_dir_string();
 
-    // First, check if current directory itself is a git repo
-    if let Some(info) = collect_git_info_for_path(&cwd) {
-        let mut info = info;
-        info.repo_path = ".".to_string();
-        repos.push(info);
-        return repos; // If root is a git repo, don't scan subdirectories
+    // If root is a git repo, don't scan subdirectories
+    if is_git_work_tree(&cwd) {
+        return vec![".".to_string()];
     }
 
     // Get paths from config or auto-detect
@@ -189,23 +286,52 @@ pub fn collect_git_repos(config: &Config) -> Vec<GitInfo> {
     let explicit_paths = git_config.and_then(|g| g.paths.clone());
     let scan_depth = git_config.and_then(|g| g.scan_depth).unwrap_or(2);
 
-    let paths_to_check: Vec<String> = if let Some(paths) = explicit_paths {
+    if let Some(paths) = explicit_paths {
         paths
     } else if auto_detect {
-        find_git_repos(&cwd, scan_depth)
+        let excludes = git_config
+            .and_then(|g| g.exclude.clone())
+            .unwrap_or_default();
+        find_git_repos(&cwd, scan_depth, &ScanFilter::new(&cwd, &excludes))
     } else {
         Vec::new()
-    };
+    }
+}
+
+/// Resolve a path returned by `discover_repo_paths` against the current directory
+pub fn resolve_repo_path(cwd: &str, path: &str) -> String {
+    if path == "." {
+        cwd.to_string()
+    } else if Path::new(path).is_absolute() {
+        path.to_string()
+    } else {
+        format!("{}/{}", cwd, path)
+    }
+}
+
+fn current_dir_string() -> String {
+    std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| ".".to_string())
+}
+
+/// Collect git info from multiple repositories based on config
+pub fn collect_git_repos(config: &Config) -> Vec<GitInfo> {
+    let mut repos = Vec::new();
+    let cwd = current_dir_string();
+
+    let git_config = config.git.as_ref();
+    let include_clean = git_config.and_then(|g| g.include_clean).unwrap_or(true);
+    let max_repos = git_config.and_then(|g| g.max_repos).unwrap_or(10);
 
     // Collect info from each path
-    for path in paths_to_check {
-        let full_path = if Path::new(&path).is_absolute() {
-            path.clone()
-        } else {
-            format!("{}/{}", cwd, path)
-        };
+    for path in discover_repo_paths(config) {
+        let full_path = resolve_repo_path(&cwd, &path);
 
         if let Some(mut info) = collect_git_info_for_path(&full_path) {
+            if !include_clean && !info.is_dirty {
+                continue;
+            }
             info.repo_path = path;
             repos.push(info);
         }
@@ -215,7 +341,131 @@ pub fn collect_git_repos(config: &Config) -> Vec<GitInfo> {
     repos.sort_by(|a, b| a.repo_path.cmp(&b.repo_path));
 
     // Limit to reasonable number
-    repos.truncate(10);
+    repos.truncate(max_repos);
 
     repos
 }
+
+/// Collect staged, unstaged and untracked files from a single repository
+pub fn collect_changed_files(repo_path: &str) -> Vec<WorkingFile> {
+    let output = match std::process::Command::new("git")
+        .args(["-C", repo_path, "status", "--porcelain", "-z"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+
+    parse_porcelain_z(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `git status --porcelain -z` output
+fn parse_porcelain_z(status: &str) -> Vec<WorkingFile> {
+    let mut files = Vec::new();
+    let mut records = status.split('\0');
+
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+
+        let mut codes = record.chars();
+        let x = codes.next().unwrap_or(' ');
+        let y = codes.next().unwrap_or(' ');
+        let path = &record[3..];
+
+        // Renames and copies are followed by the original path
+        if x == 'R' || x == 'C' {
+            records.next();
+        }
+
+        let (change, staged) = if x == '?' {
+            ("untracked", false)
+        } else if x == 'U' || y == 'U' || (x == 'A' && y == 'A') || (x == 'D' && y == 'D') {
+            ("conflict", false)
+        } else if matches!(x, 'R' | 'C' | 'A') {
+            // A staged rename/copy/add edited again (`RM`, `AM`) is still that change
+            (change_kind(x), true)
+        } else if y != ' ' {
+            (change_kind(y), x != ' ')
+        } else {
+            (change_kind(x), true)
+        };
+
+        files.push(WorkingFile {
+            path: path.to_string(),
+            change: change.to_string(),
+            staged,
+        });
+    }
+
+    files
+}
+
+/// Map a porcelain status letter to a change kind
+fn change_kind(code: char) -> &'static str {
+    match code {
+        'A' => "added",
+        'D' => "deleted",
+        'R' => "renamed",
+        'C' => "copied",
+        'T' => "typechange",
+        _ => "modified",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(extra: &[&str]) -> ScanFilter {
+        let extra: Vec<String> = extra.iter().map(|s| s.to_string()).collect();
+        ScanFilter::new("/nonexistent", &extra)
+    }
+
+    #[test]
+    fn test_parse_porcelain_z() {
+        let status = "M  staged.rs\0 M unstaged.rs\0MM both.rs\0R  new.rs\0old.rs\0RM moved.rs\0was.rs\0AM fresh.rs\0?? notes/\0UU merge.rs\0";
+        let files = parse_porcelain_z(status);
+        let summary: Vec<(&str, &str, bool)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.change.as_str(), f.staged))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("staged.rs", "modified", true),
+                ("unstaged.rs", "modified", false),
+                ("both.rs", "modified", true),
+                ("new.rs", "renamed", true),
+                ("moved.rs", "renamed", true),
+                ("fresh.rs", "added", true),
+                ("notes/", "untracked", false),
+                ("merge.rs", "conflict", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_excludes() {
+        let f = filter(&[]);
+        assert!(f.is_excluded("node_modules", "node_modules"));
+        assert!(f.is_excluded("app/target", "target"));
+        assert!(f.is_excluded(".repo", ".repo"));
+        assert!(!f.is_excluded("frameworks", "frameworks"));
+    }
+
+    #[test]
+    fn test_gitignore_style_excludes() {
+        let f = filter(&["build/", "/install", "log", "src/*/tmp", "!src/keep/tmp"]);
+        assert!(f.is_excluded("build", "build"));
+        assert!(f.is_excluded("src/pkg/build", "build"));
+        assert!(f.is_excluded("install", "install"));
+        assert!(!f.is_excluded("src/install", "install"));
+        assert!(f.is_excluded("src/pkg/log", "log"));
+        assert!(f.is_excluded("src/pkg/tmp", "tmp"));
+        assert!(!f.is_excluded("src/a/b/tmp", "tmp"));
+        assert!(!f.is_excluded("src/keep/tmp", "tmp"));
+    }
+}
@@ -1,12 +1,16 @@
 //! History collector - tracks relevant commands via hook-captured logs
 
+use super::environment::derive_environments;
+use super::shell_history::{is_shell_entry, read_shell_history};
 use super::traits::Collector;
 use crate::config::Config;
 use crate::context::{Context, HistoryEntry};
+use crate::history_log::HistoryLog;
+use crate::redact::Redactor;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use regex::Regex;
-use std::fs;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 /// Command history collector
 #[derive(Debug, Default)]
@@ -26,67 +30,602 @@ impl Collector for HistoryCollector {
     }
 
     fn collect(&self, config: &Config, ctx: &mut Context) {
-        ctx.command_history = collect_command_history(config);
+        let options = match HistoryOptions::from_config(config) {
+            Some(options) => options,
+            None => return,
+        };
+
+        let (mut entries, window) = read_project_history(config, &options);
+        ctx.environments = derive_environments(&entries);
+        let recent = entries.split_off(entries.len() - window);
+        ctx.command_history = select_history(recent, &options);
     }
 }
 
-/// Collect command history from log file
-fn collect_command_history(config: &Config) -> Vec<HistoryEntry> {
-    let history_config = match &config.history {
-        Some(hc) if hc.enabled.unwrap_or(true) => hc,
-        _ => return Vec::new(),
-    };
+/// Upper bound on log entries scanned per collection
+const MAX_SCAN_ENTRIES: usize = 5000;
+
+/// `[history]` settings resolved with defaults
+struct HistoryOptions {
+    patterns: Vec<Regex>,
+    pinned: Vec<Regex>,
+    max_entries: usize,
+    dedup: bool,
+    rank_by: RankBy,
+    project_dir: Option<PathBuf>,
+    shell_sources: Vec<String>,
+}
 
-    let log_file = history_config.log_file.clone().unwrap_or_else(|| {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        format!("{}/.contextkeeper/command-history.jsonl", home)
-    });
+impl HistoryOptions {
+    /// Returns None when history is not configured or disabled
+    fn from_config(config: &Config) -> Option<Self> {
+        let history_config = match &config.history {
+            Some(hc) if hc.enabled.unwrap_or(true) => hc,
+            _ => return None,
+        };
 
-    let max_entries = history_config.max_entries.unwrap_or(20);
+        let default_patterns = vec![
+            r"lunch\s+\S+".to_string(),
+            r"source\s+.*envsetup".to_string(),
+            r"export\s+\w+=".to_string(),
+            r"m\s+\S+".to_string(),
+            r"mm\b".to_string(),
+            r"mma\b".to_string(),
+        ];
 
-    let default_patterns = vec![
-        r"lunch\s+\S+".to_string(),
-        r"source\s+.*envsetup".to_string(),
-        r"export\s+\w+=".to_string(),
-        r"m\s+\S+".to_string(),
-        r"mm\b".to_string(),
-        r"mma\b".to_string(),
-    ];
+        let default_pinned = vec![
+            r"lunch\s+\S+".to_string(),
+            r"source\s+\S+".to_string(),
+            r"export\s+\w+=".to_string(),
+        ];
 
-    let patterns = history_config.patterns.clone().unwrap_or(default_patterns);
+        let compile = |patterns: Vec<String>| -> Vec<Regex> {
+            patterns.iter().filter_map(|p| Regex::new(p).ok()).collect()
+        };
 
-    let compiled_patterns: Vec<Regex> =
-        patterns.iter().filter_map(|p| Regex::new(p).ok()).collect();
+        Some(Self {
+            patterns: compile(history_config.patterns.clone().unwrap_or(default_patterns)),
+            pinned: compile(history_config.pinned.clone().unwrap_or(default_pinned)),
+            max_entries: history_config.max_entries.unwrap_or(20),
+            dedup: history_config.dedup.unwrap_or(true),
+            rank_by: RankBy::parse(history_config.rank_by.as_deref()),
+            project_dir: if history_config.project_only.unwrap_or(true) {
+                std::env::current_dir().ok()
+            } else {
+                None
+            },
+            shell_sources: history_config.shell_history.clone().unwrap_or_default(),
+        })
+    }
+
+    fn matches(&self, command: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|re| re.is_match(command))
+    }
+}
 
+/// Read logged commands for this project, redacted, in chronological order
+///
+/// Returns the entries together with the size of the display window: the
+/// newest entries that already hold enough matching commands (and every
+/// pinned pattern) for recency ranking. The environment replay needs the
+/// whole newest session, so reading continues past the window until an entry
+/// of an older session shows up. Archives are only decompressed once the
+/// live log runs out before that point.
+fn read_project_history(config: &Config, options: &HistoryOptions) -> (Vec<HistoryEntry>, usize) {
+    let shell_entries = read_shell_history(&options.shell_sources);
+    let merged = merge_newest_first(
+        HistoryLog::from_config(config).iter_rev(),
+        shell_entries.into_iter().rev(),
+    );
+    scan_history(merged, &Redactor::from_config(config), options)
+}
+
+/// Collect in-project entries from a newest-first stream (see `read_project_history`)
+fn scan_history(
+    newest_first: impl Iterator<Item = HistoryEntry>,
+    redactor: &Redactor,
+    options: &HistoryOptions,
+) -> (Vec<HistoryEntry>, usize) {
     let mut entries = Vec::new();
-    let path = Path::new(&log_file);
+    let mut window = None;
+    let mut matched = 0;
+    let mut distinct = HashSet::new();
+    let mut pinned_found = vec![false; options.pinned.len()];
+    let mut newest_session: Option<String> = None;
 
-    if !path.exists() {
-        return entries;
+    for mut entry in newest_first.take(MAX_SCAN_ENTRIES) {
+        if !is_in_project(&entry, options.project_dir.as_deref()) {
+            continue;
+        }
+        // Shell history has no real sessions and is interleaved with the hook log
+        if !is_shell_entry(&entry) {
+            let session = newest_session.get_or_insert_with(|| entry.session_id.clone());
+            if window.is_some() && entry.session_id != *session {
+                break;
+            }
+        }
+        entry.command = redactor.redact(&entry.command);
+
+        if window.is_none() && options.matches(&entry.command) {
+            matched += 1;
+            distinct.insert(entry.command.clone());
+            for (found, re) in pinned_found.iter_mut().zip(&options.pinned) {
+                *found |= re.is_match(&entry.command);
+            }
+        }
+        entries.push(entry);
+
+        let enough = if options.dedup { distinct.len() } else { matched } >= options.max_entries;
+        if window.is_none()
+            && options.rank_by == RankBy::Recency
+            && enough
+            && pinned_found.iter().all(|f| *f)
+        {
+            window = Some(entries.len());
+        }
     }
 
-    if let Ok(file) = fs::File::open(path) {
-        let reader = io::BufReader::new(file);
+    entries.reverse();
+    let window = window.unwrap_or(entries.len());
+    (entries, window)
+}
+
+/// Merge two newest-first streams into one, ordered by timestamp
+fn merge_newest_first(
+    a: impl Iterator<Item = HistoryEntry>,
+    b: impl Iterator<Item = HistoryEntry>,
+) -> impl Iterator<Item = HistoryEntry> {
+    let mut a = a.peekable();
+    let mut b = b.peekable();
+
+    std::iter::from_fn(move || match (a.peek(), b.peek()) {
+        (Some(x), Some(y)) if entry_time(x) >= entry_time(y) => a.next(),
+        (Some(_), Some(_)) => b.next(),
+        (Some(_), None) => a.next(),
+        (None, _) => b.next(),
+    })
+}
+
+/// Parsed timestamp for ordering (unparseable timestamps sort oldest)
+fn entry_time(entry: &HistoryEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Select relevant commands from the project history
+fn select_history(entries: Vec<HistoryEntry>, options: &HistoryOptions) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = entries
+        .into_iter()
+        .filter(|entry| options.matches(&entry.command))
+        .collect();
 
-        for line in reader.lines().map_while(Result::ok) {
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&line) {
-                let command = json["command"].as_str().unwrap_or("");
-                let matches_pattern = compiled_patterns.is_empty()
-                    || compiled_patterns.iter().any(|re| re.is_match(command));
+    if options.dedup {
+        entries = dedup_entries(entries);
+    }
+
+    select_entries(entries, options.max_entries, options.rank_by, &options.pinned)
+}
 
-                if matches_pattern && !command.is_empty() {
-                    entries.push(HistoryEntry {
-                        timestamp: json["timestamp"].as_str().unwrap_or("").to_string(),
-                        command: command.to_string(),
-                    });
-                }
+/// Ranking used to decide which entries survive the max_entries cut
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RankBy {
+    Recency,
+    Frequency,
+}
+
+impl RankBy {
+    fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("frequency") => RankBy::Frequency,
+            _ => RankBy::Recency,
+        }
+    }
+}
+
+/// Collapse identical commands, keeping the most recent occurrence and a run count
+///
+/// Input and output are in chronological order.
+fn dedup_entries(entries: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in &entries {
+        *counts.entry(entry.command.clone()).or_insert(0) += 1;
+    }
+
+    let mut seen = HashSet::new();
+    let mut deduped: Vec<HistoryEntry> = entries
+        .into_iter()
+        .rev()
+        .filter(|entry| seen.insert(entry.command.clone()))
+        .map(|mut entry| {
+            entry.count = counts[&entry.command];
+            entry
+        })
+        .collect();
+    deduped.reverse();
+    deduped
+}
+
+/// Trim chronological entries to `max_entries`
+///
+/// The most recent match of each pinned pattern is always kept; remaining
+/// slots are filled by recency or frequency. Output stays chronological.
+fn select_entries(
+    entries: Vec<HistoryEntry>,
+    max_entries: usize,
+    rank_by: RankBy,
+    pinned: &[Regex],
+) -> Vec<HistoryEntry> {
+    if entries.len() <= max_entries {
+        return entries;
+    }
+
+    let mut keep: Vec<usize> = Vec::new();
+    for re in pinned {
+        if let Some(idx) = entries.iter().rposition(|e| re.is_match(&e.command)) {
+            if !keep.contains(&idx) && keep.len() < max_entries {
+                keep.push(idx);
             }
         }
     }
 
-    if entries.len() > max_entries {
-        entries.drain(0..entries.len() - max_entries);
+    let mut ranked: Vec<usize> = (0..entries.len()).rev().collect();
+    if rank_by == RankBy::Frequency {
+        // Stable sort keeps the recency order among equal counts
+        ranked.sort_by(|a, b| entries[*b].count.cmp(&entries[*a].count));
+    }
+
+    for idx in ranked {
+        if keep.len() >= max_entries {
+            break;
+        }
+        if !keep.contains(&idx) {
+            keep.push(idx);
+        }
     }
 
+    keep.sort_unstable();
     entries
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| keep.binary_search(idx).is_ok())
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Check whether an entry was run inside the project directory
+///
+/// Hook log lines without a recorded cwd (older versions) are kept. Shell
+/// history entries never record one and may come from any project, so they
+/// only appear with `project_only = false`.
+fn is_in_project(entry: &HistoryEntry, project_dir: Option<&Path>) -> bool {
+    match project_dir {
+        Some(_) if is_shell_entry(entry) => false,
+        Some(dir) if !entry.cwd.is_empty() => Path::new(&entry.cwd).starts_with(dir),
+        _ => true,
+    }
+}
+
+/// Filters for searching the full command log
+#[derive(Debug, Default)]
+pub struct HistoryQuery {
+    /// Substring (case-insensitive) or regex, depending on `regex`
+    pub pattern: String,
+    pub regex: bool,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only commands run in this directory or below
+    pub cwd: Option<String>,
+    pub session_id: Option<String>,
+    pub limit: usize,
+}
+
+/// Search the full command log, including rotated archives, newest first
+pub fn search_command_history(
+    config: &Config,
+    query: &HistoryQuery,
+) -> Result<Vec<HistoryEntry>, regex::Error> {
+    let matcher = if query.regex {
+        Regex::new(&query.pattern)?
+    } else {
+        Regex::new(&format!("(?i){}", regex::escape(&query.pattern)))?
+    };
+
+    let redactor = Redactor::from_config(config);
+    let mut results = Vec::new();
+
+    for mut entry in HistoryLog::from_config(config).iter_rev() {
+        if results.len() >= query.limit {
+            break;
+        }
+
+        if query.since.is_some() || query.until.is_some() {
+            let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Utc),
+                Err(_) => continue,
+            };
+            // The log is chronological, so nothing older can match
+            if query.since.is_some_and(|since| ts < since) {
+                break;
+            }
+            if query.until.is_some_and(|until| ts > until) {
+                continue;
+            }
+        }
+
+        if let Some(cwd) = &query.cwd {
+            if !Path::new(&entry.cwd).starts_with(cwd) {
+                continue;
+            }
+        }
+        if let Some(session) = &query.session_id {
+            if &entry.session_id != session {
+                continue;
+            }
+        }
+
+        entry.command = redactor.redact(&entry.command);
+        if matcher.is_match(&entry.command) {
+            results.push(entry);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Parse a time bound: RFC 3339, a date (`2026-02-27`), or a relative age (`30m`, `12h`, `7d`)
+pub fn parse_time_bound(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc());
+    }
+
+    let (unit_start, _) = value.char_indices().last()?;
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: i64 = amount.parse().ok()?;
+    let age = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return None,
+    };
+    Some(Utc::now() - age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(commands: &[&str]) -> Vec<HistoryEntry> {
+        commands
+            .iter()
+            .enumerate()
+            .map(|(i, cmd)| HistoryEntry {
+                timestamp: format!("t{}", i),
+                command: cmd.to_string(),
+                count: 1,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<(&str, usize)> {
+        entries.iter().map(|e| (e.command.as_str(), e.count)).collect()
+    }
+
+    #[test]
+    fn test_dedup_keeps_last_occurrence_with_count() {
+        let input = entries(&["lunch a", "m -j32", "mm", "m -j32", "m -j32"]);
+        let deduped = dedup_entries(input);
+        assert_eq!(
+            commands(&deduped),
+            vec![("lunch a", 1), ("mm", 1), ("m -j32", 3)]
+        );
+        assert_eq!(deduped[2].timestamp, "t4");
+    }
+
+    #[test]
+    fn test_select_keeps_pinned_lunch() {
+        let input = entries(&["lunch aosp_x86_64-userdebug", "m a", "m b", "m c", "m d"]);
+        let pinned = vec![Regex::new(r"lunch\s+\S+").unwrap()];
+
+        let selected = select_entries(input, 3, RankBy::Recency, &pinned);
+        assert_eq!(
+            commands(&selected),
+            vec![("lunch aosp_x86_64-userdebug", 1), ("m c", 1), ("m d", 1)]
+        );
+    }
+
+    #[test]
+    fn test_select_by_frequency() {
+        let mut input = entries(&["m a", "m b", "m c"]);
+        input[0].count = 5;
+        input[1].count = 1;
+        input[2].count = 2;
+
+        let selected = select_entries(input, 2, RankBy::Frequency, &[]);
+        assert_eq!(commands(&selected), vec![("m a", 5), ("m c", 2)]);
+    }
+
+    #[test]
+    fn test_environment_survives_display_cutoff() {
+        let options = HistoryOptions {
+            patterns: vec![
+                Regex::new(r"lunch\s+\S+").unwrap(),
+                Regex::new(r"m\s+\S+").unwrap(),
+            ],
+            pinned: Vec::new(),
+            max_entries: 3,
+            dedup: true,
+            rank_by: RankBy::Recency,
+            project_dir: None,
+            shell_sources: Vec::new(),
+        };
+        let mut log = vec!["source build/envsetup.sh", "lunch aosp_x86_64-userdebug"];
+        log.extend(["m a", "m b", "m c", "m d", "m e"]);
+        log.extend(["lunch older_session-eng", "m older"]);
+        let mut input = entries(&log);
+        for (i, entry) in input.iter_mut().enumerate() {
+            entry.session_id = if i < 7 { "s1" } else { "s0" }.to_string();
+        }
+        // s0 ran before s1 started
+        input.rotate_left(7);
+
+        let (all, window) = scan_history(input.into_iter().rev(), &Redactor::default(), &options);
+        assert_eq!(all.len(), 7);
+        assert_eq!(window, 3);
+        assert!(all.iter().all(|e| e.session_id == "s1"));
+
+        let envs = derive_environments(&all);
+        assert_eq!(envs[0].lunch_target, "aosp_x86_64-userdebug");
+        assert_eq!(envs[0].sourced, vec!["build/envsetup.sh"]);
+    }
+
+    #[test]
+    fn test_parse_time_bound() {
+        let ts = parse_time_bound("2026-02-27T10:30:00Z").unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-02-27T10:30:00+00:00");
+
+        let date = parse_time_bound("2026-02-27").unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-02-27T00:00:00+00:00");
+
+        let day_ago = parse_time_bound("1d").unwrap();
+        assert!((Utc::now() - day_ago - Duration::days(1)).num_seconds().abs() < 5);
+
+        assert!(parse_time_bound("yesterday").is_none());
+        assert!(parse_time_bound("1日").is_none());
+        assert!(parse_time_bound("").is_none());
+    }
+
+    #[test]
+    fn test_search_command_history_across_archives() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("ck-search-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let line = |ts: &str, command: &str, cwd: &str, session: &str| {
+            format!(
+                "{}\n",
+                serde_json::json!({"timestamp": ts, "command": command, "cwd": cwd, "session_id": session})
+            )
+        };
+
+        // The archive is older than the live log, except for one out-of-order
+        // entry that a `since` search must never reach
+        let archived = [
+            line("2026-03-01T10:00:00Z", "m stray", "/src/aosp", "s1"),
+            line("2026-01-05T10:00:00Z", "make -j8 kernel", "/src/aosp", "s1"),
+            line("2026-01-06T10:00:00Z", "m droid", "/src/aosp", "s1"),
+        ]
+        .concat();
+        let archive = dir.join("command-history-20260107T000000.000000Z.jsonl.gz");
+        let mut encoder = GzEncoder::new(
+            std::fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(archived.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let live = [
+            line("2026-02-01T10:00:00Z", "m updater", "/src/aosp/packages", "s2"),
+            line("2026-02-02T10:00:00Z", "cargo build", "/src/tool", "s2"),
+            line("2026-02-03T10:00:00Z", "m droid", "/src/aosp", "s3"),
+        ]
+        .concat();
+        let log_file = dir.join("command-history.jsonl");
+        std::fs::write(&log_file, live).unwrap();
+
+        let config: Config = toml::from_str(&format!(
+            "[history]\nlog_file = \"{}\"\n",
+            log_file.display()
+        ))
+        .unwrap();
+        let search = |query: HistoryQuery| -> Vec<String> {
+            search_command_history(&config, &query)
+                .unwrap()
+                .into_iter()
+                .map(|e| format!("{} {}", e.timestamp, e.command))
+                .collect()
+        };
+        let query = |pattern: &str| HistoryQuery {
+            pattern: pattern.to_string(),
+            limit: 100,
+            ..Default::default()
+        };
+
+        // Substring search is case-insensitive and reads through into the archive
+        assert_eq!(
+            search(query("M DROID")),
+            vec!["2026-02-03T10:00:00Z m droid", "2026-01-06T10:00:00Z m droid"]
+        );
+        assert_eq!(
+            search(HistoryQuery {
+                regex: true,
+                ..query(r"^m (droid|updater)$")
+            }),
+            vec![
+                "2026-02-03T10:00:00Z m droid",
+                "2026-02-01T10:00:00Z m updater",
+                "2026-01-06T10:00:00Z m droid",
+            ]
+        );
+        assert!(search_command_history(
+            &config,
+            &HistoryQuery {
+                regex: true,
+                ..query("(")
+            }
+        )
+        .is_err());
+
+        assert_eq!(
+            search(HistoryQuery {
+                cwd: Some("/src/aosp/packages".to_string()),
+                ..query("")
+            }),
+            vec!["2026-02-01T10:00:00Z m updater"]
+        );
+        assert_eq!(
+            search(HistoryQuery {
+                session_id: Some("s2".to_string()),
+                ..query("")
+            }),
+            vec!["2026-02-02T10:00:00Z cargo build", "2026-02-01T10:00:00Z m updater"]
+        );
+
+        // `since` stops at the first older entry instead of scanning the archive
+        assert_eq!(
+            search(HistoryQuery {
+                since: parse_time_bound("2026-02-01"),
+                ..query("m ")
+            }),
+            vec!["2026-02-03T10:00:00Z m droid", "2026-02-01T10:00:00Z m updater"]
+        );
+        assert_eq!(
+            search(HistoryQuery {
+                until: parse_time_bound("2026-02-02"),
+                ..query("m ")
+            }),
+            vec![
+                "2026-02-01T10:00:00Z m updater",
+                "2026-01-06T10:00:00Z m droid",
+            ]
+        );
+        assert_eq!(
+            search(HistoryQuery {
+                limit: 2,
+                ..query("")
+            }),
+            vec!["2026-02-03T10:00:00Z m droid", "2026-02-02T10:00:00Z cargo build"]
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
@@ -4,19 +4,36 @@
 //! development context from various sources.
 
 mod traits;
+mod assignments;
 mod build;
+mod entry_point;
 mod container;
+mod devcontainer;
+mod compose;
+mod environment;
 mod git;
 mod history;
+mod shell_history;
 mod adb;
+mod ros;
+mod yocto;
+mod cargo;
+mod buildsystem;
 mod workstate;
+mod active_target;
 
 pub use traits::Collector;
 pub use build::BuildCollector;
-pub use container::ContainerCollector;
+pub use container::{ContainerCollector, default_runtime};
+pub use compose::ComposeCollector;
 pub use git::GitCollector;
-pub use history::HistoryCollector;
+pub use history::{HistoryCollector, HistoryQuery, search_command_history, parse_time_bound};
 pub use adb::AdbCollector;
+pub use ros::RosCollector;
+pub use yocto::YoctoCollector;
+pub use cargo::CargoCollector;
+pub use buildsystem::BuildSystemCollector;
+pub use active_target::{clear_active_target, load_active_target, save_active_target};
 pub use workstate::{
     WorkStateCollector,
     save_work_state_to_file,
@@ -30,19 +47,48 @@ use crate::context::Context;
 pub fn default_collectors() -> Vec<Box<dyn Collector>> {
     vec![
         Box::new(BuildCollector),
+        // Before containers so stopped service containers get inspected too
+        Box::new(ComposeCollector),
         Box::new(ContainerCollector),
         Box::new(GitCollector),
         Box::new(HistoryCollector),
         Box::new(AdbCollector),
+        // After history so the sourced distro can be taken from it
+        Box::new(RosCollector),
+        Box::new(YoctoCollector),
+        Box::new(CargoCollector),
+        Box::new(BuildSystemCollector),
         Box::new(WorkStateCollector),
     ]
 }
 
+/// Render a runnable command with its description as a trailing shell comment
+fn format_command(command: String, description: &str) -> String {
+    if description.is_empty() {
+        command
+    } else {
+        format!("{}  # {}", command, description)
+    }
+}
+
 /// Collect all context data using the collector registry
 pub fn collect_context(config: &Config) -> Context {
     collect_context_with(config, &default_collectors())
 }
 
+/// Collect only build targets, skipping containers, git and history
+///
+/// Enough to validate a target name without querying every runtime.
+pub fn collect_targets(config: &Config) -> Context {
+    let collectors: Vec<Box<dyn Collector>> = vec![
+        Box::new(BuildCollector),
+        Box::new(RosCollector),
+        Box::new(YoctoCollector),
+        Box::new(CargoCollector),
+    ];
+    collect_context_with(config, &collectors)
+}
+
 /// Collect context data using a custom set of collectors
 pub fn collect_context_with(config: &Config, collectors: &[Box<dyn Collector>]) -> Context {
     let mut ctx = Context::default();
@@ -65,6 +111,10 @@ pub fn collect_context_with(config: &Config, collectors: &[Box<dyn Collector>])
         }
     }
 
+    // Targets are collected before history, so mark the active one last
+    ctx.active_target = load_active_target();
+    build::mark_active_target(&mut ctx);
+
     ctx
 }
 
@@ -75,14 +125,19 @@ mod tests {
     #[test]
     fn test_default_collectors() {
         let collectors = default_collectors();
-        assert_eq!(collectors.len(), 6);
+        assert_eq!(collectors.len(), 11);
 
         let names: Vec<&str> = collectors.iter().map(|c| c.name()).collect();
         assert!(names.contains(&"build"));
         assert!(names.contains(&"container"));
+        assert!(names.contains(&"compose"));
         assert!(names.contains(&"git"));
         assert!(names.contains(&"history"));
         assert!(names.contains(&"adb"));
+        assert!(names.contains(&"ros"));
+        assert!(names.contains(&"yocto"));
+        assert!(names.contains(&"cargo"));
+        assert!(names.contains(&"buildsystem"));
         assert!(names.contains(&"workstate"));
     }
 
@@ -0,0 +1,341 @@
+//! ROS 2 collector - colcon workspace packages, install state and last build
+
+use super::traits::Collector;
+use crate::config::Config;
+use crate::context::{ColconBuildResult, Context, RosPackage, RosWorkspace};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How deep below `src/` to look for `package.xml`
+const PACKAGE_SEARCH_DEPTH: usize = 5;
+
+/// Upper bound on files checked per package when looking for stale sources
+const MAX_STALE_SCAN_FILES: usize = 5000;
+
+/// Dependency tags read from package.xml
+const DEPEND_TAGS: &[&str] = &[
+    "depend",
+    "build_depend",
+    "build_export_depend",
+    "exec_depend",
+    "buildtool_depend",
+];
+
+/// ROS 2 / colcon workspace collector
+#[derive(Debug, Default)]
+pub struct RosCollector;
+
+impl Collector for RosCollector {
+    fn name(&self) -> &'static str {
+        "ros"
+    }
+
+    fn is_enabled(&self, config: &Config) -> bool {
+        // Enabled by default for ROS projects, can be disabled via config
+        config
+            .ros
+            .as_ref()
+            .and_then(|r| r.enabled)
+            .unwrap_or_else(|| {
+                config
+                    .project
+                    .as_ref()
+                    .and_then(|p| p.project_type.as_deref())
+                    .is_some_and(|t| t == "ros" || t == "ros2")
+            })
+    }
+
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let root = config
+            .ros
+            .as_ref()
+            .and_then(|r| r.workspace.clone())
+            .unwrap_or_else(|| ".".to_string());
+        let root = Path::new(&root);
+
+        let workspace = RosWorkspace {
+            distro: detect_distro(ctx),
+            packages: collect_packages(root),
+            last_build: read_latest_build(&root.join("log")),
+        };
+
+        if !workspace.packages.is_empty() || workspace.last_build.is_some() {
+            ctx.ros = Some(workspace);
+        }
+    }
+}
+
+/// ROS distro from the environment, command history, or the only one installed
+fn detect_distro(ctx: &Context) -> String {
+    if let Ok(distro) = std::env::var("ROS_DISTRO") {
+        if !distro.is_empty() {
+            return distro;
+        }
+    }
+
+    if let Some(env) = ctx.environments.iter().find(|e| !e.ros_distro.is_empty()) {
+        return env.ros_distro.clone();
+    }
+
+    let installed: Vec<String> = fs::read_dir("/opt/ros")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().join("setup.bash").exists())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    match installed.as_slice() {
+        [only] => only.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Find packages under `src/` and check them against `install/`
+fn collect_packages(root: &Path) -> Vec<RosPackage> {
+    let mut manifests = Vec::new();
+    find_package_manifests(&root.join("src"), PACKAGE_SEARCH_DEPTH, &mut manifests);
+
+    let mut packages: Vec<RosPackage> = manifests
+        .iter()
+        .filter_map(|manifest| {
+            let content = fs::read_to_string(manifest).ok()?;
+            let mut package = parse_package_xml(&content)?;
+            let dir = manifest.parent()?;
+            package.path = dir.strip_prefix(root).unwrap_or(dir).display().to_string();
+
+            if let Some(installed_at) = install_time(&root.join("install"), &package.name) {
+                package.built = true;
+                package.stale = newest_source_change(dir).is_some_and(|t| t > installed_at);
+            }
+            Some(package)
+        })
+        .collect();
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+/// Collect `package.xml` paths, honoring `COLCON_IGNORE` and not descending into packages
+fn find_package_manifests(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    if dir.join("COLCON_IGNORE").exists() {
+        return;
+    }
+
+    let manifest = dir.join("package.xml");
+    if manifest.is_file() {
+        out.push(manifest);
+        return;
+    }
+
+    if depth == 0 {
+        return;
+    }
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        let mut dirs: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        dirs.sort();
+
+        for sub in dirs {
+            find_package_manifests(&sub, depth - 1, out);
+        }
+    }
+}
+
+/// Extract name, build type and dependencies from package.xml
+fn parse_package_xml(content: &str) -> Option<RosPackage> {
+    let comments = Regex::new(r"(?s)<!--.*?-->").ok()?;
+    let content = comments.replace_all(content, "");
+
+    let tag_text = |tag: &str| -> Vec<String> {
+        Regex::new(&format!(r"<{}(?:\s[^>]*)?>\s*([^<]*?)\s*</{}>", tag, tag))
+            .map(|re| {
+                re.captures_iter(&content)
+                    .map(|c| c[1].to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let name = tag_text("name").into_iter().next()?;
+    let build_type = tag_text("build_type")
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "catkin".to_string());
+
+    let mut dependencies: Vec<String> = Vec::new();
+    for tag in DEPEND_TAGS {
+        for dep in tag_text(tag) {
+            if !dependencies.contains(&dep) {
+                dependencies.push(dep);
+            }
+        }
+    }
+
+    Some(RosPackage {
+        name,
+        build_type,
+        dependencies,
+        ..Default::default()
+    })
+}
+
+/// When colcon last installed `package` (isolated or merged install layout)
+fn install_time(install: &Path, package: &str) -> Option<SystemTime> {
+    [
+        install
+            .join(package)
+            .join("share/colcon-core/packages")
+            .join(package),
+        install.join("share/colcon-core/packages").join(package),
+    ]
+    .iter()
+    .find_map(|marker| fs::metadata(marker).and_then(|m| m.modified()).ok())
+}
+
+/// Most recent modification time of any file in a package source tree
+fn newest_source_change(dir: &Path) -> Option<SystemTime> {
+    let mut newest: Option<SystemTime> = None;
+    let mut stack = vec![dir.to_path_buf()];
+    let mut scanned = 0;
+
+    while let Some(dir) = stack.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let meta = match entry.metadata() {
+                Ok(meta) => meta,
+                Err(_) => continue,
+            };
+            if meta.is_dir() {
+                stack.push(entry.path());
+                continue;
+            }
+
+            if let Ok(modified) = meta.modified() {
+                newest = newest.max(Some(modified));
+            }
+            scanned += 1;
+            if scanned >= MAX_STALE_SCAN_FILES {
+                return newest;
+            }
+        }
+    }
+
+    newest
+}
+
+/// Read the result of the last `colcon build` from `log/latest_build/events.log`
+fn read_latest_build(log_dir: &Path) -> Option<ColconBuildResult> {
+    let latest = log_dir.join("latest_build");
+    let events = fs::read_to_string(latest.join("events.log")).ok()?;
+
+    // latest_build links to e.g. build_2024-01-15_10-30-00
+    let started_at = fs::read_link(&latest)
+        .ok()
+        .and_then(|target| {
+            let name = target.file_name()?.to_string_lossy().to_string();
+            let stamp = name.strip_prefix("build_")?;
+            let (date, time) = stamp.split_once('_')?;
+            Some(format!("{} {}", date, time.replace('-', ":")))
+        })
+        .unwrap_or_default();
+
+    let mut result = parse_build_events(&events);
+    result.started_at = started_at;
+    Some(result)
+}
+
+/// Summarize `JobStarted`/`JobEnded` events per package
+fn parse_build_events(events: &str) -> ColconBuildResult {
+    let event = Regex::new(r"^\[[\d.]+\] \(([^)]+)\) (JobStarted|JobEnded): (.*)$").expect("regex");
+    let rc = Regex::new(r"'rc': (-?\d+)").expect("regex");
+    let mut result = ColconBuildResult::default();
+
+    for line in events.lines() {
+        let caps = match event.captures(line) {
+            Some(caps) => caps,
+            None => continue,
+        };
+        let package = caps[1].to_string();
+
+        if &caps[2] == "JobStarted" {
+            if !result.incomplete.contains(&package) {
+                result.incomplete.push(package);
+            }
+            continue;
+        }
+
+        result.incomplete.retain(|p| *p != package);
+        let success = rc
+            .captures(&caps[3])
+            .is_some_and(|c| c[1].parse::<i32>() == Ok(0));
+        if success {
+            result.succeeded.push(package);
+        } else {
+            result.failed.push(package);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_xml() {
+        let xml = r#"<?xml version="1.0"?>
+<package format="3">
+  <name>nav_bringup</name>
+  <version>0.1.0</version>
+  <!-- <depend>commented_out</depend> -->
+  <buildtool_depend>ament_cmake</buildtool_depend>
+  <depend>rclcpp</depend>
+  <depend>nav_msgs</depend>
+  <exec_depend condition="$ROS_VERSION == 2">launch_ros</exec_depend>
+  <test_depend>ament_lint_auto</test_depend>
+  <export>
+    <build_type>ament_cmake</build_type>
+  </export>
+</package>"#;
+
+        let package = parse_package_xml(xml).unwrap();
+        assert_eq!(package.name, "nav_bringup");
+        assert_eq!(package.build_type, "ament_cmake");
+        assert_eq!(
+            package.dependencies,
+            vec!["rclcpp", "nav_msgs", "launch_ros", "ament_cmake"]
+        );
+    }
+
+    #[test]
+    fn test_parse_build_events() {
+        let events = "[0.000000] (-) TimerEvent: {}\n\
+[0.100000] (msgs) JobStarted: {'identifier': 'msgs'}\n\
+[0.200000] (driver) JobStarted: {'identifier': 'driver'}\n\
+[5.000000] (msgs) JobEnded: {'identifier': 'msgs', 'rc': 0}\n\
+[5.100000] (planner) JobStarted: {'identifier': 'planner'}\n\
+[9.000000] (driver) JobEnded: {'identifier': 'driver', 'rc': 2}\n";
+
+        let result = parse_build_events(events);
+        assert_eq!(result.succeeded, vec!["msgs"]);
+        assert_eq!(result.failed, vec!["driver"]);
+        assert_eq!(result.incomplete, vec!["planner"]);
+    }
+}
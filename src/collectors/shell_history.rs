@@ -0,0 +1,257 @@
+//! Shell history import - reads bash, zsh and fish history files
+//!
+//! Lets commands typed in the user's own terminal (not just those run by the
+//! agent through the hook) reach the history collector.
+
+use crate::context::HistoryEntry;
+use crate::history_log::{expand_home, home_dir};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::PathBuf;
+
+/// Only the most recent entries of each history file are imported
+const MAX_ENTRIES_PER_FILE: usize = 5000;
+
+/// Session id prefix of imported entries, e.g. `shell:zsh`
+const SHELL_SESSION_PREFIX: &str = "shell:";
+
+/// Read configured shell histories, oldest first
+///
+/// Each source is a shell name (`bash`, `zsh`, `fish`), optionally followed by
+/// `:<path>` to override the default history file location. Entries without a
+/// timestamp are skipped because they cannot be ordered against the hook log.
+pub fn read_shell_history(sources: &[String]) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+
+    for source in sources {
+        let (shell, path) = match source.split_once(':') {
+            Some((shell, path)) => (shell, expand_home(path)),
+            None => match default_history_path(source) {
+                Some(path) => (source.as_str(), path),
+                None => continue,
+            },
+        };
+
+        let content = match fs::read(&path) {
+            // zsh stores metafied bytes, so decode lossily
+            Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+            Err(_) => continue,
+        };
+
+        let mut parsed = match shell {
+            "bash" => parse_bash_history(&content),
+            "zsh" => parse_zsh_history(&content),
+            "fish" => parse_fish_history(&content),
+            _ => continue,
+        };
+
+        if parsed.len() > MAX_ENTRIES_PER_FILE {
+            parsed.drain(..parsed.len() - MAX_ENTRIES_PER_FILE);
+        }
+        for entry in &mut parsed {
+            entry.session_id = format!("{}{}", SHELL_SESSION_PREFIX, shell);
+        }
+        entries.extend(parsed);
+    }
+
+    entries.sort_by_key(|e| e.timestamp.clone());
+    entries
+}
+
+/// Entries imported from a shell history file rather than logged by the hook
+///
+/// They have no working directory, so they cannot be tied to a project.
+pub fn is_shell_entry(entry: &HistoryEntry) -> bool {
+    entry.session_id.starts_with(SHELL_SESSION_PREFIX)
+}
+
+/// Default history file for each supported shell
+fn default_history_path(shell: &str) -> Option<PathBuf> {
+    let home = home_dir();
+    match shell {
+        "bash" => Some(PathBuf::from(format!("{}/.bash_history", home))),
+        "zsh" => {
+            let dir = std::env::var("ZDOTDIR").unwrap_or(home);
+            Some(PathBuf::from(format!("{}/.zsh_history", dir)))
+        }
+        "fish" => {
+            let data =
+                std::env::var("XDG_DATA_HOME").unwrap_or_else(|_| format!("{}/.local/share", home));
+            Some(PathBuf::from(format!("{}/fish/fish_history", data)))
+        }
+        _ => None,
+    }
+}
+
+/// Format a Unix timestamp the same way the hook logger does
+fn format_epoch(secs: i64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(secs, 0).map(|ts| ts.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+fn entry(timestamp: String, command: &str) -> HistoryEntry {
+    HistoryEntry {
+        timestamp,
+        command: command.to_string(),
+        count: 1,
+        ..Default::default()
+    }
+}
+
+/// Parse `~/.bash_history` written with `HISTTIMEFORMAT` (`#<epoch>` before each command)
+fn parse_bash_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut timestamp: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(epoch) = line
+            .strip_prefix('#')
+            .and_then(|s| s.trim().parse::<i64>().ok())
+        {
+            timestamp = format_epoch(epoch);
+            continue;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Multi-line commands (cmdhist/lithist) run until the next `#<epoch>`
+        match (timestamp.take(), entries.last_mut()) {
+            (Some(ts), _) => entries.push(entry(ts, line)),
+            (None, Some(last)) => {
+                last.command.push('\n');
+                last.command.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+
+    entries
+}
+
+/// Parse zsh extended history (`: <epoch>:<duration>;<command>`)
+fn parse_zsh_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut continued = false;
+
+    for line in content.lines() {
+        // Lines ending in a backslash continue onto the next line
+        if continued {
+            if let Some(last) = entries.last_mut() {
+                last.command.push('\n');
+                last.command.push_str(line.trim_end_matches('\\'));
+            }
+            continued = line.ends_with('\\');
+            continue;
+        }
+
+        let rest = match line.strip_prefix(": ") {
+            Some(rest) => rest,
+            None => continue,
+        };
+        let (meta, command) = match rest.split_once(';') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let (epoch, duration) = meta.split_once(':').unwrap_or((meta, "0"));
+        let timestamp = match epoch.trim().parse::<i64>().ok().and_then(format_epoch) {
+            Some(ts) => ts,
+            None => continue,
+        };
+
+        let mut e = entry(timestamp, command.trim_end_matches('\\'));
+        e.duration_ms = duration.trim().parse::<u64>().ok().map(|s| s * 1000);
+        entries.push(e);
+        continued = command.ends_with('\\');
+    }
+
+    entries
+}
+
+/// Parse fish history (`- cmd: ...` / `  when: <epoch>` records)
+fn parse_fish_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut command: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(cmd) = line.strip_prefix("- cmd: ") {
+            command = Some(unescape_fish(cmd));
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            let timestamp = when.trim().parse::<i64>().ok().and_then(format_epoch);
+            if let (Some(cmd), Some(ts)) = (command.take(), timestamp) {
+                entries.push(entry(ts, &cmd));
+            }
+        }
+    }
+
+    entries
+}
+
+/// Undo fish's history escaping (`\\` and `\n`)
+fn unescape_fish(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<(&str, &str)> {
+        entries
+            .iter()
+            .map(|e| (e.timestamp.as_str(), e.command.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_bash_history_with_timestamps() {
+        let content = "ls\n#1700000000\nlunch aosp_x86_64-userdebug\n#1700000060\nm -j32\n\
+                       #1700000120\nfor d in a b; do\n  make -C $d\ndone\n";
+        assert_eq!(
+            commands(&parse_bash_history(content)),
+            vec![
+                ("2023-11-14T22:13:20Z", "lunch aosp_x86_64-userdebug"),
+                ("2023-11-14T22:14:20Z", "m -j32"),
+                ("2023-11-14T22:15:20Z", "for d in a b; do\n  make -C $d\ndone"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_zsh_extended_history() {
+        let content = ": 1700000000:12;colcon build\n: 1700000060:0;echo a \\\nb\nplain line\n";
+        let entries = parse_zsh_history(content);
+        assert_eq!(
+            commands(&entries),
+            vec![
+                ("2023-11-14T22:13:20Z", "colcon build"),
+                ("2023-11-14T22:14:20Z", "echo a \nb"),
+            ]
+        );
+        assert_eq!(entries[0].duration_ms, Some(12000));
+    }
+
+    #[test]
+    fn test_parse_fish_history() {
+        let content = "- cmd: bitbake core-image-minimal\n  when: 1700000000\n  paths:\n    - conf\n- cmd: echo a\\\\nb\n  when: 1700000060\n";
+        assert_eq!(
+            commands(&parse_fish_history(content)),
+            vec![
+                ("2023-11-14T22:13:20Z", "bitbake core-image-minimal"),
+                ("2023-11-14T22:14:20Z", "echo a\\nb"),
+            ]
+        );
+    }
+}
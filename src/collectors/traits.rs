@@ -9,6 +9,7 @@ use crate::context::Context;
 /// development context (build targets, containers, git repos, etc.)
 pub trait Collector: Send + Sync {
     /// Returns the collector's name for logging and debugging
+    #[allow(dead_code)]
     fn name(&self) -> &'static str;
 
     /// Check if this collector is enabled based on configuration
@@ -1,8 +1,10 @@
 //! Work state collector - saves and loads work state for context recovery
 
+use super::git::{collect_changed_files, discover_repo_paths, resolve_repo_path};
 use super::traits::Collector;
 use crate::config::Config;
 use crate::context::{Context, TodoItem, WorkState};
+use crate::redact::Redactor;
 use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
@@ -21,11 +23,22 @@ impl Collector for WorkStateCollector {
         true
     }
 
-    fn collect(&self, _config: &Config, ctx: &mut Context) {
-        ctx.work_state = load_work_state_with_hooks();
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let redactor = Redactor::from_config(config);
+        ctx.work_state = load_work_state_with_hooks().map(|state| redact_work_state(state, &redactor));
     }
 }
 
+/// Redact secrets from free-text work state fields before formatting
+fn redact_work_state(mut state: WorkState, redactor: &Redactor) -> WorkState {
+    state.task_summary = redactor.redact(&state.task_summary);
+    state.notes = redactor.redact(&state.notes);
+    for todo in &mut state.todos {
+        todo.content = redactor.redact(&todo.content);
+    }
+    state
+}
+
 /// Get the path to the work state file
 pub fn get_work_state_path() -> String {
     let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
@@ -141,33 +154,29 @@ pub fn load_work_state_with_hooks() -> Option<WorkState> {
     Some(state)
 }
 
-/// Collect working files from git diff (for PreCompact hook)
-pub fn collect_working_files() -> Vec<String> {
-    let mut files = Vec::new();
+/// Collect working files from git status across all repositories (for PreCompact hook)
+///
+/// Covers staged, unstaged and untracked files in the repositories found by
+/// the git collector, each annotated with its change kind.
+pub fn collect_working_files(config: &Config) -> Vec<String> {
+    let max_files = config
+        .workstate
+        .as_ref()
+        .and_then(|w| w.max_files)
+        .unwrap_or(20);
     let cwd = std::env::current_dir()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_else(|_| ".".to_string());
 
-    // Try to get modified files from all git repos
-    if let Ok(output) = std::process::Command::new("bash")
-        .args(["-c", &format!(
-            "cd '{}' && find . -maxdepth 3 -name '.git' -type d 2>/dev/null | while read gitdir; do \
-             repo=$(dirname \"$gitdir\"); \
-             git -C \"$repo\" diff --name-only 2>/dev/null | sed \"s|^|$repo/|\" ; \
-             done | head -20",
-            cwd
-        )])
-        .output()
-    {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            for line in stdout.lines() {
-                let line = line.trim();
-                if !line.is_empty() {
-                    // Clean up path (remove leading ./)
-                    let clean_path = line.trim_start_matches("./");
-                    files.push(clean_path.to_string());
-                }
+    let mut files = Vec::new();
+    for repo in discover_repo_paths(config) {
+        for mut file in collect_changed_files(&resolve_repo_path(&cwd, &repo)) {
+            if repo != "." {
+                file.path = format!("{}/{}", repo.trim_end_matches('/'), file.path);
+            }
+            files.push(file.display());
+            if files.len() >= max_files {
+                return files;
             }
         }
     }
@@ -0,0 +1,263 @@
+//! Yocto collector - BitBake build configuration, image recipes and last build
+
+use super::assignments::{parse_assignments, parse_conf_assignments};
+use super::traits::Collector;
+use crate::config::Config;
+use crate::context::{BitbakeResult, BuildTarget, Context, YoctoBuild};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Upper bound on image recipes reported as build targets
+const MAX_IMAGE_RECIPES: usize = 50;
+
+/// Yocto/BitBake build directory collector
+#[derive(Debug, Default)]
+pub struct YoctoCollector;
+
+impl Collector for YoctoCollector {
+    fn name(&self) -> &'static str {
+        "yocto"
+    }
+
+    fn is_enabled(&self, config: &Config) -> bool {
+        // Enabled by default for Yocto projects, can be disabled via config
+        config
+            .yocto
+            .as_ref()
+            .and_then(|y| y.enabled)
+            .unwrap_or_else(|| {
+                config
+                    .project
+                    .as_ref()
+                    .and_then(|p| p.project_type.as_deref())
+                    .is_some_and(|t| t == "yocto")
+            })
+    }
+
+    fn collect(&self, config: &Config, ctx: &mut Context) {
+        let build_dir = config
+            .yocto
+            .as_ref()
+            .and_then(|y| y.build_dir.clone())
+            .unwrap_or_else(|| "build".to_string());
+
+        let build = match read_build_config(Path::new(&build_dir)) {
+            Some(build) => build,
+            None => return,
+        };
+
+        for mut image in find_image_recipes(&build.layers) {
+            // QEMU machines can boot images with runqemu
+            image.can_emulator = build.machine.starts_with("qemu");
+            if !ctx.targets.iter().any(|t| t.name == image.name) {
+                ctx.targets.push(image);
+            }
+        }
+        ctx.yocto = Some(build);
+    }
+}
+
+/// Read `conf/local.conf`, `conf/bblayers.conf` and the latest cooker log
+fn read_build_config(build_dir: &Path) -> Option<YoctoBuild> {
+    let local = fs::read_to_string(build_dir.join("conf/local.conf")).ok()?;
+    let (local, defaults) = parse_conf_assignments(&local);
+    let get = |vars: &[(String, String)], name: &str| {
+        vars.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+    };
+
+    // BitBake sets TOPDIR to the build directory
+    let topdir = fs::canonicalize(build_dir)
+        .unwrap_or_else(|_| build_dir.to_path_buf())
+        .display()
+        .to_string();
+
+    let layers = fs::read_to_string(build_dir.join("conf/bblayers.conf"))
+        .ok()
+        .and_then(|content| get(&parse_assignments(&content), "BBLAYERS"))
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(|layer| layer.replace("${TOPDIR}", &topdir))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let machine = resolve_machine(
+        get(&local, "MACHINE"),
+        defaults.iter().any(|n| n == "MACHINE"),
+        std::env::var("MACHINE").ok(),
+    );
+    let tmp_dir = get(&local, "TMPDIR")
+        .map(|dir| dir.replace("${TOPDIR}", &topdir))
+        .unwrap_or_else(|| format!("{}/tmp", topdir));
+
+    Some(YoctoBuild {
+        build_dir: build_dir.display().to_string(),
+        machine,
+        distro: get(&local, "DISTRO").unwrap_or_default(),
+        layers,
+        last_build: read_last_bitbake(&Path::new(&tmp_dir).join("log/cooker")),
+    })
+}
+
+/// The MACHINE BitBake builds for: `MACHINE = ...` in local.conf beats the
+/// environment, which in turn beats a `?=`/`??=` default
+fn resolve_machine(local: Option<String>, is_default: bool, env: Option<String>) -> String {
+    let env = env.filter(|m| !m.is_empty());
+    match (local, env) {
+        (Some(local), _) if !is_default => local,
+        (_, Some(env)) => env,
+        (local, None) => local.unwrap_or_default(),
+    }
+}
+
+/// Image recipes (`recipes-*/images/*.bb`) provided by the configured layers
+fn find_image_recipes(layers: &[String]) -> Vec<BuildTarget> {
+    let mut targets: Vec<BuildTarget> = Vec::new();
+
+    for layer in layers {
+        let layer_name = Path::new(layer)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| layer.clone());
+        let pattern = format!("{}/recipes-*/images/*.bb", layer);
+
+        let mut recipes: Vec<PathBuf> = glob::glob(&pattern)
+            .map(|paths| paths.flatten().collect())
+            .unwrap_or_default();
+        recipes.sort();
+
+        for recipe in recipes {
+            let name = match recipe.file_stem() {
+                Some(stem) => stem.to_string_lossy().to_string(),
+                None => continue,
+            };
+            if targets.iter().any(|t| t.name == name) {
+                continue;
+            }
+            let mut target = BuildTarget {
+                name,
+                description: format!("image recipe ({})", layer_name),
+                ..Default::default()
+            };
+            target.extra.insert("kind".to_string(), "image".to_string());
+            targets.push(target);
+        }
+    }
+
+    targets.truncate(MAX_IMAGE_RECIPES);
+    targets
+}
+
+/// Parse the most recently written log under `tmp/log/cooker/<machine>/`
+fn read_last_bitbake(cooker_dir: &Path) -> Option<BitbakeResult> {
+    let mut logs: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(cooker_dir)
+        .ok()?
+        .flatten()
+        .filter_map(|machine| fs::read_dir(machine.path()).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "log"))
+        .filter_map(|p| Some((fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    logs.sort();
+
+    let (_, path) = logs.pop()?;
+    let content = fs::read_to_string(&path).ok()?;
+    let mut result = parse_cooker_log(&content);
+    result.log_file = path.display().to_string();
+    Some(result)
+}
+
+/// Extract the task summary and failed tasks from a cooker console log
+fn parse_cooker_log(content: &str) -> BitbakeResult {
+    let summary = Regex::new(
+        r"Tasks Summary: Attempted (\d+) tasks of which (\d+) didn't need to be rerun and (?:all succeeded|(\d+) failed)",
+    )
+    .expect("regex");
+    let failed_task = Regex::new(r"ERROR: Task \(([^)]+)\) failed").expect("regex");
+
+    let mut result = BitbakeResult::default();
+
+    for line in content.lines() {
+        if let Some(caps) = summary.captures(line) {
+            result.completed = true;
+            result.attempted = caps[1].parse().unwrap_or(0);
+        } else if let Some(caps) = failed_task.captures(line) {
+            // "/path/meta/recipes-core/busybox/busybox_1.36.1.bb:do_compile" -> "busybox_1.36.1:do_compile"
+            let task = caps[1].rsplit('/').next().unwrap_or(&caps[1]);
+            let task = task.replacen(".bb:", ":", 1);
+            if !result.failed_tasks.contains(&task) {
+                result.failed_tasks.push(task);
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cooker_log() {
+        let log = "NOTE: Executing Tasks\n\
+ERROR: busybox-1.36.1-r0 do_compile: oe_runmake failed\n\
+ERROR: Task (/work/poky/meta/recipes-core/busybox/busybox_1.36.1.bb:do_compile) failed with exit code '1'\n\
+NOTE: Tasks Summary: Attempted 4321 tasks of which 4000 didn't need to be rerun and 1 failed.\n";
+
+        let result = parse_cooker_log(log);
+        assert!(result.completed);
+        assert_eq!(result.attempted, 4321);
+        assert_eq!(result.failed_tasks, vec!["busybox_1.36.1:do_compile"]);
+
+        let interrupted = parse_cooker_log("NOTE: Executing Tasks\n");
+        assert!(!interrupted.completed);
+    }
+
+    #[test]
+    fn test_resolve_machine() {
+        let qemu = || Some("qemux86-64".to_string());
+        let env = || Some("raspberrypi4-64".to_string());
+
+        assert_eq!(resolve_machine(qemu(), false, env()), "qemux86-64");
+        assert_eq!(resolve_machine(qemu(), true, env()), "raspberrypi4-64");
+        assert_eq!(resolve_machine(qemu(), true, Some(String::new())), "qemux86-64");
+        assert_eq!(resolve_machine(None, false, env()), "raspberrypi4-64");
+        assert_eq!(resolve_machine(None, false, None), "");
+    }
+
+    #[test]
+    fn test_read_build_dir() {
+        let root = std::env::temp_dir().join(format!("ck-yocto-{}", std::process::id()));
+        let build = root.join("build");
+        let layer = root.join("meta-custom");
+        fs::create_dir_all(build.join("conf")).unwrap();
+        fs::create_dir_all(layer.join("recipes-core/images")).unwrap();
+        fs::write(layer.join("recipes-core/images/custom-image.bb"), "").unwrap();
+        fs::write(
+            build.join("conf/local.conf"),
+            "MACHINE ??= \"qemux86-64\"\nMACHINE = \"raspberrypi4-64\"\nDISTRO ?= \"poky\"\n",
+        )
+        .unwrap();
+        fs::write(
+            build.join("conf/bblayers.conf"),
+            "BBLAYERS ?= \" \\\n  ${TOPDIR}/../meta-custom \\\n  \"\n",
+        )
+        .unwrap();
+
+        let config = read_build_config(&build).unwrap();
+        assert_eq!(config.machine, "raspberrypi4-64");
+        assert_eq!(config.distro, "poky");
+        assert_eq!(config.layers.len(), 1);
+        assert!(config.layers[0].ends_with("build/../meta-custom"));
+        assert!(config.last_build.is_none());
+
+        let images = find_image_recipes(&config.layers);
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, "custom-image");
+        assert_eq!(images[0].description, "image recipe (meta-custom)");
+    }
+}
@@ -1,6 +1,7 @@
 //! Configuration loading and structures
 
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -14,6 +15,13 @@ pub struct Config {
     pub history: Option<HistoryConfig>,
     pub git: Option<GitConfig>,
     pub adb: Option<AdbConfig>,
+    pub workstate: Option<WorkStateConfig>,
+    pub ros: Option<RosConfig>,
+    pub yocto: Option<YoctoConfig>,
+    pub cargo: Option<CargoConfig>,
+    pub buildsystem: Option<BuildSystemConfig>,
+    pub compose: Option<ComposeConfig>,
+    pub targets: Option<Vec<TargetConfig>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -28,13 +36,46 @@ pub struct ScriptsConfig {
     pub entry_point: Option<String>,
     pub config_dir: Option<String>,
     pub config_pattern: Option<String>,
-    #[allow(dead_code)]
+    /// Extra variables to capture from KEY=value target files
     pub extract_vars: Option<Vec<String>>,
 }
 
+/// Build target defined as a `[[targets]]` table or in a JSON/YAML target file
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct TargetConfig {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(alias = "container")]
+    pub container_name: Option<String>,
+    #[serde(alias = "lunch")]
+    pub lunch_target: Option<String>,
+    pub can_emulator: Option<bool>,
+    pub can_flash: Option<bool>,
+    /// Any other fields, preserved and shown in the full output
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ContainersConfig {
     pub runtime: Option<String>,
+    /// Runtimes to query in parallel; overrides `runtime` when set
+    pub runtimes: Option<Vec<RuntimeConfig>>,
+    /// Containers builds must run in, reported even when stopped
+    pub expected: Option<Vec<String>>,
+}
+
+/// A container runtime, optionally talking to a remote engine
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct RuntimeConfig {
+    /// Runtime binary: "podman" (default) or "docker"
+    pub runtime: Option<String>,
+    /// podman system connection name (`podman --connection`)
+    pub connection: Option<String>,
+    /// Engine URL (`podman --url` / `docker --host`)
+    pub host: Option<String>,
+    /// docker context name (`docker --context`)
+    pub context: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,6 +89,28 @@ pub struct HistoryConfig {
     pub log_file: Option<String>,
     pub patterns: Option<Vec<String>>,
     pub max_entries: Option<usize>,
+    /// Only show commands run inside the current project directory (default: true)
+    pub project_only: Option<bool>,
+    /// Redact secrets from history and work state output (default: true)
+    pub redact: Option<bool>,
+    /// Additional regexes to redact (first capture group, or whole match)
+    pub redact_patterns: Option<Vec<String>>,
+    /// Collapse identical commands into one entry with a count (default: true)
+    pub dedup: Option<bool>,
+    /// Ranking used when trimming to max_entries: "recency" (default) or "frequency"
+    pub rank_by: Option<String>,
+    /// Patterns whose most recent match is always kept regardless of volume
+    pub pinned: Option<Vec<String>>,
+    /// Rotate the live log when it exceeds this size in KiB (default: 1024)
+    pub rotate_size_kb: Option<u64>,
+    /// Rotate the live log when its oldest entry is older than this (default: 30)
+    pub rotate_days: Option<u64>,
+    /// Number of compressed archives to keep (default: 10)
+    pub keep_archives: Option<usize>,
+    /// Delete archives older than this many days (default: 180)
+    pub archive_days: Option<u64>,
+    /// Shell histories to merge in: "bash", "zsh", "fish" (optionally "zsh:/path/to/file")
+    pub shell_history: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -58,6 +121,12 @@ pub struct GitConfig {
     pub auto_detect: Option<bool>,
     /// Max depth for auto-detection (default: 2)
     pub scan_depth: Option<usize>,
+    /// Extra directories to skip during auto-detection (.gitignore-style patterns)
+    pub exclude: Option<Vec<String>>,
+    /// Maximum number of repositories to report (default: 10)
+    pub max_repos: Option<usize>,
+    /// Include repositories without changes (default: true)
+    pub include_clean: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,6 +135,54 @@ pub struct AdbConfig {
     pub enabled: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RosConfig {
+    /// Enable/disable the colcon workspace collector (default: on for type = "ros")
+    pub enabled: Option<bool>,
+    /// Workspace root containing src/, install/ and log/ (default: ".")
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YoctoConfig {
+    /// Enable/disable the BitBake build directory collector (default: on for type = "yocto")
+    pub enabled: Option<bool>,
+    /// Build directory containing conf/local.conf (default: "build")
+    pub build_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CargoConfig {
+    /// Enable/disable the Cargo workspace collector (default: on for type = "cargo")
+    pub enabled: Option<bool>,
+    /// Directory containing the workspace Cargo.toml (default: ".")
+    pub root: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BuildSystemConfig {
+    /// Enable/disable CMake/Make/just/Task discovery (default: true)
+    pub enabled: Option<bool>,
+    /// Directory containing CMakePresets.json, Makefile, etc. (default: ".")
+    pub root: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComposeConfig {
+    /// Enable/disable compose file discovery (default: true)
+    pub enabled: Option<bool>,
+    /// Compose files to read, in override order (default: compose.yaml and friends)
+    pub files: Option<Vec<String>>,
+    /// Project name (default: `name:` in the file, COMPOSE_PROJECT_NAME or the directory name)
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkStateConfig {
+    /// Max number of auto-detected working files (default: 20)
+    pub max_files: Option<usize>,
+}
+
 /// Read configuration from file
 pub fn read_config() -> Config {
     let paths = [
@@ -1,6 +1,7 @@
 //! Context data structures
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Build target information
 #[derive(Debug, Default, Clone)]
@@ -11,6 +12,12 @@ pub struct BuildTarget {
     pub lunch_target: String,
     pub can_emulator: bool,
     pub can_flash: bool,
+    /// Additional fields from structured target definitions
+    pub extra: BTreeMap<String, String>,
+    /// Variables listed in `scripts.extract_vars`, in that order
+    pub variables: Vec<(String, String)>,
+    /// True if this target matches the lunch target currently in use
+    pub active: bool,
 }
 
 /// Container information
@@ -19,13 +26,213 @@ pub struct ContainerInfo {
     pub name: String,
     pub status: String,
     pub runtime: String,
+    pub running: bool,
+    /// Exit code and finish time of a stopped container
+    pub exit_code: Option<i32>,
+    pub finished_at: String,
+    /// Image name the container was created from
+    pub image: String,
+    pub created: String,
+    pub working_dir: String,
+    pub mounts: Vec<ContainerMount>,
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Bind mount or volume of a container
+#[derive(Debug, Default, Clone)]
+pub struct ContainerMount {
+    /// "bind", "volume" or "tmpfs"
+    pub kind: String,
+    pub source: String,
+    pub destination: String,
+    pub read_only: bool,
 }
 
 /// Command history entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
 pub struct HistoryEntry {
     pub timestamp: String,
     pub command: String,
+    pub cwd: String,
+    pub session_id: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: Option<u64>,
+    /// Number of times this command was run (after deduplication)
+    pub count: usize,
+}
+
+impl HistoryEntry {
+    /// True if the command is known to have exited with a non-zero status
+    pub fn failed(&self) -> bool {
+        matches!(self.exit_code, Some(code) if code != 0)
+    }
+}
+
+/// Effective shell environment derived from command history
+#[derive(Debug, Default, Clone)]
+pub struct ActiveEnvironment {
+    pub session_id: String,
+    pub cwd: String,
+    pub updated_at: String,
+    pub lunch_target: String,
+    pub ros_distro: String,
+    /// Sourced setup scripts, oldest first
+    pub sourced: Vec<String>,
+    /// Exported variables plus MACHINE/DISTRO assignments, oldest first
+    pub variables: Vec<(String, String)>,
+}
+
+impl ActiveEnvironment {
+    pub fn is_empty(&self) -> bool {
+        self.lunch_target.is_empty()
+            && self.ros_distro.is_empty()
+            && self.sourced.is_empty()
+            && self.variables.is_empty()
+    }
+}
+
+/// Package found in a ROS 2 / colcon workspace
+#[derive(Debug, Default, Clone)]
+pub struct RosPackage {
+    pub name: String,
+    pub path: String,
+    /// `ament_cmake`, `ament_python`, `cmake`, ...
+    pub build_type: String,
+    pub dependencies: Vec<String>,
+    /// True if the package has been installed into `install/`
+    pub built: bool,
+    /// True if sources changed after the last install
+    pub stale: bool,
+}
+
+/// Outcome of the most recent `colcon build`
+#[derive(Debug, Default, Clone)]
+pub struct ColconBuildResult {
+    pub started_at: String,
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+    /// Packages that started but never finished (aborted or interrupted)
+    pub incomplete: Vec<String>,
+}
+
+/// ROS 2 / colcon workspace state
+#[derive(Debug, Default, Clone)]
+pub struct RosWorkspace {
+    pub distro: String,
+    pub packages: Vec<RosPackage>,
+    pub last_build: Option<ColconBuildResult>,
+}
+
+/// Outcome of the most recent `bitbake` run
+#[derive(Debug, Default, Clone)]
+pub struct BitbakeResult {
+    pub log_file: String,
+    /// False if the log has no task summary (interrupted or still running)
+    pub completed: bool,
+    pub attempted: usize,
+    /// Failed tasks as `recipe:task`
+    pub failed_tasks: Vec<String>,
+}
+
+/// Yocto build directory configuration
+#[derive(Debug, Default, Clone)]
+pub struct YoctoBuild {
+    pub build_dir: String,
+    pub machine: String,
+    pub distro: String,
+    /// Layer paths from BBLAYERS
+    pub layers: Vec<String>,
+    pub last_build: Option<BitbakeResult>,
+}
+
+/// Package in a Cargo workspace
+#[derive(Debug, Default, Clone)]
+pub struct CargoPackage {
+    pub name: String,
+    pub path: String,
+    pub has_lib: bool,
+    pub binaries: Vec<String>,
+    /// Integration tests (`tests/*.rs` and `[[test]]`)
+    pub tests: Vec<String>,
+    pub features: Vec<String>,
+}
+
+/// Cargo workspace layout and toolchain
+#[derive(Debug, Default, Clone)]
+pub struct CargoWorkspace {
+    pub packages: Vec<CargoPackage>,
+    pub target_dir: String,
+    /// Channel pinned in rust-toolchain(.toml), empty if not pinned
+    pub toolchain: String,
+}
+
+/// Preset from CMakePresets.json / CMakeUserPresets.json
+#[derive(Debug, Default, Clone)]
+pub struct CmakePreset {
+    pub name: String,
+    /// `configure`, `build` or `test`
+    pub kind: String,
+    pub description: String,
+    pub generator: String,
+    pub binary_dir: String,
+    /// Configure preset used by a build/test preset
+    pub configure_preset: String,
+}
+
+/// Configured CMake or Ninja build directory
+#[derive(Debug, Default, Clone)]
+pub struct CmakeBuildDir {
+    pub path: String,
+    pub generator: String,
+    pub build_type: String,
+}
+
+/// CMake presets and configured build directories
+#[derive(Debug, Default, Clone)]
+pub struct BuildSystemInfo {
+    pub cmake_presets: Vec<CmakePreset>,
+    pub build_dirs: Vec<CmakeBuildDir>,
+}
+
+/// Dev Container definition from devcontainer.json
+#[derive(Debug, Default, Clone)]
+pub struct DevContainer {
+    pub config_file: String,
+    pub image: String,
+    pub dockerfile: String,
+    pub remote_user: String,
+    pub workspace_folder: String,
+    pub post_create_command: String,
+    /// Mounts as `source -> target`
+    pub mounts: Vec<String>,
+    /// Running container created from this definition, if any
+    pub container: String,
+    /// Runtime command of that container
+    pub runtime: String,
+}
+
+/// Service defined in a compose file
+#[derive(Debug, Default, Clone)]
+pub struct ComposeService {
+    pub name: String,
+    pub image: String,
+    /// Build context when the image is built locally
+    pub build: String,
+    /// Actual container name when it exists, otherwise the expected one
+    pub container_name: String,
+    pub profiles: Vec<String>,
+    /// `running`, `stopped` or `not running`
+    pub state: String,
+    /// Runtime command of the running container
+    pub runtime: String,
+}
+
+/// Docker Compose / podman-compose project
+#[derive(Debug, Default, Clone)]
+pub struct ComposeProject {
+    pub name: String,
+    pub files: Vec<String>,
+    pub services: Vec<ComposeService>,
 }
 
 /// Git repository information
@@ -47,6 +254,25 @@ pub struct AdbDevice {
     pub device_type: String, // "adb" or "fastboot"
 }
 
+/// File with uncommitted changes, as reported by git status
+#[derive(Debug, Clone, Default)]
+pub struct WorkingFile {
+    pub path: String,
+    pub change: String, // "modified", "added", "deleted", "renamed", "untracked", "conflict", ...
+    pub staged: bool,
+}
+
+impl WorkingFile {
+    /// Display form stored in work state, e.g. "src/main.rs (staged, modified)"
+    pub fn display(&self) -> String {
+        if self.staged {
+            format!("{} (staged, {})", self.path, self.change)
+        } else {
+            format!("{} ({})", self.path, self.change)
+        }
+    }
+}
+
 /// Todo item for work state
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TodoItem {
@@ -65,6 +291,15 @@ pub struct WorkState {
     pub todos: Vec<TodoItem>,
 }
 
+/// Build target chosen with set_active_target, persisted per project
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ActiveTarget {
+    pub name: String,
+    pub container_name: String,
+    pub lunch_target: String,
+    pub set_at: String,
+}
+
 /// Aggregated development context
 #[derive(Debug, Default, Clone)]
 pub struct Context {
@@ -72,10 +307,24 @@ pub struct Context {
     pub project_type: String,
     pub targets: Vec<BuildTarget>,
     pub containers: Vec<ContainerInfo>,
+    /// Containers listed in `[containers] expected`
+    pub expected_containers: Vec<String>,
+    /// Runtimes that could not be queried (command prefix, reason)
+    pub unreachable_runtimes: Vec<(String, String)>,
+    pub devcontainer: Option<DevContainer>,
     pub available_commands: Vec<String>,
     pub hints: String,
     pub command_history: Vec<HistoryEntry>,
+    /// Derived environments per session, most recent first
+    pub environments: Vec<ActiveEnvironment>,
     pub git_repos: Vec<GitInfo>,
     pub adb_devices: Vec<AdbDevice>,
+    pub ros: Option<RosWorkspace>,
+    pub yocto: Option<YoctoBuild>,
+    pub cargo: Option<CargoWorkspace>,
+    pub build_system: Option<BuildSystemInfo>,
+    pub compose: Option<ComposeProject>,
     pub work_state: Option<WorkState>,
+    /// Explicitly selected target, which overrides the one inferred from lunch
+    pub active_target: Option<ActiveTarget>,
 }
@@ -1,7 +1,11 @@
 //! Full formatter (~1000 tokens) - complete information
 
 use crate::context::Context;
-use super::{format_git_status, format_work_state};
+use super::{
+    container_state, format_bitbake_result, format_colcon_result, format_compose_service,
+    format_container_warnings, format_environment_summary, format_git_status,
+    format_history_result, format_work_state,
+};
 
 /// Full format (~1000 tokens) - complete information
 pub fn format_full(ctx: &Context) -> String {
@@ -33,13 +37,52 @@ pub fn format_full(ctx: &Context) -> String {
     // Build targets
     if !ctx.targets.is_empty() {
         out.push_str("## Available Build Targets\n\n");
-        out.push_str("| Target | Description | Container | Lunch Target |\n");
-        out.push_str("|--------|-------------|-----------|---------------|\n");
+        // One extra column per extracted variable, in first-seen order
+        let mut var_names: Vec<&str> = Vec::new();
+        for (name, _) in ctx.targets.iter().flat_map(|t| &t.variables) {
+            if !var_names.contains(&name.as_str()) {
+                var_names.push(name);
+            }
+        }
+
+        out.push_str("| Target | Description | Container | Lunch Target |");
+        for name in &var_names {
+            out.push_str(&format!(" {} |", name));
+        }
+        out.push_str("\n|--------|-------------|-----------|---------------|");
+        for _ in &var_names {
+            out.push_str("---|");
+        }
+        out.push('\n');
         for target in &ctx.targets {
+            let name = if target.active {
+                format!("**{}** (active)", target.name)
+            } else {
+                target.name.clone()
+            };
+            let container = if target.container_name.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "{} ({})",
+                    target.container_name,
+                    container_state(&ctx.containers, &target.container_name)
+                )
+            };
             out.push_str(&format!(
-                "| {} | {} | {} | {} |\n",
-                target.name, target.description, target.container_name, target.lunch_target
+                "| {} | {} | {} | {} |",
+                name, target.description, container, target.lunch_target
             ));
+            for name in &var_names {
+                let value = target
+                    .variables
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, v)| v.as_str())
+                    .unwrap_or("");
+                out.push_str(&format!(" {} |", value));
+            }
+            out.push('\n');
         }
         out.push('\n');
 
@@ -66,20 +109,262 @@ pub fn format_full(ctx: &Context) -> String {
             }
         }
         out.push('\n');
+
+        let detailed: Vec<_> = ctx.targets.iter().filter(|t| !t.extra.is_empty()).collect();
+        if !detailed.is_empty() {
+            out.push_str("### Target Details\n");
+            for target in detailed {
+                let fields: Vec<String> = target
+                    .extra
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                out.push_str(&format!("- **{}:** {}\n", target.name, fields.join(", ")));
+            }
+            out.push('\n');
+        }
+    }
+
+    // ROS workspace
+    if let Some(ros) = &ctx.ros {
+        out.push_str("## ROS Workspace\n");
+        if !ros.distro.is_empty() {
+            out.push_str(&format!("- **Distro:** {}\n", ros.distro));
+        }
+        if let Some(build) = &ros.last_build {
+            out.push_str(&format!(
+                "- **Last build:** {} {}\n",
+                format_colcon_result(build),
+                build.started_at
+            ));
+        }
+        out.push('\n');
+
+        if !ros.packages.is_empty() {
+            out.push_str("| Package | Build Type | Status | Workspace Deps |\n");
+            out.push_str("|---------|------------|--------|----------------|\n");
+            for pkg in &ros.packages {
+                let status = if !pkg.built {
+                    "not built"
+                } else if pkg.stale {
+                    "stale"
+                } else {
+                    "built"
+                };
+                // Only dependencies inside the workspace matter for --packages-up-to
+                let local_deps: Vec<&str> = pkg
+                    .dependencies
+                    .iter()
+                    .filter(|d| ros.packages.iter().any(|p| &p.name == *d))
+                    .map(|d| d.as_str())
+                    .collect();
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    pkg.name,
+                    pkg.build_type,
+                    status,
+                    local_deps.join(", ")
+                ));
+            }
+            out.push('\n');
+        }
+    }
+
+    // Yocto build
+    if let Some(yocto) = &ctx.yocto {
+        out.push_str("## Yocto Build\n");
+        out.push_str(&format!("- **Build dir:** {}\n", yocto.build_dir));
+        if !yocto.machine.is_empty() {
+            out.push_str(&format!("- **MACHINE:** {}\n", yocto.machine));
+        }
+        if !yocto.distro.is_empty() {
+            out.push_str(&format!("- **DISTRO:** {}\n", yocto.distro));
+        }
+        if let Some(result) = &yocto.last_build {
+            out.push_str(&format!(
+                "- **Last bitbake:** {} ({})\n",
+                format_bitbake_result(result),
+                result.log_file
+            ));
+        }
+        if !yocto.layers.is_empty() {
+            out.push_str("- **Layers:**\n");
+            for layer in &yocto.layers {
+                out.push_str(&format!("  - {}\n", layer));
+            }
+        }
+        out.push('\n');
+    }
+
+    // Cargo workspace
+    if let Some(cargo) = &ctx.cargo {
+        out.push_str("## Cargo Workspace\n");
+        if !cargo.toolchain.is_empty() {
+            out.push_str(&format!("- **Toolchain:** {}\n", cargo.toolchain));
+        }
+        out.push_str(&format!("- **Target dir:** {}\n\n", cargo.target_dir));
+
+        out.push_str("| Package | Path | Lib | Binaries | Tests | Features |\n");
+        out.push_str("|---------|------|-----|----------|-------|----------|\n");
+        for pkg in &cargo.packages {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                pkg.name,
+                pkg.path,
+                if pkg.has_lib { "yes" } else { "" },
+                pkg.binaries.join(", "),
+                pkg.tests.join(", "),
+                pkg.features.join(", ")
+            ));
+        }
+        out.push('\n');
+    }
+
+    // CMake presets and build directories
+    if let Some(bs) = &ctx.build_system {
+        out.push_str("## Build System\n");
+        if !bs.cmake_presets.is_empty() {
+            out.push_str("| Preset | Kind | Description | Generator / Configure | Binary Dir |\n");
+            out.push_str("|--------|------|-------------|-----------------------|------------|\n");
+            for preset in &bs.cmake_presets {
+                let base = if preset.configure_preset.is_empty() {
+                    &preset.generator
+                } else {
+                    &preset.configure_preset
+                };
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    preset.name, preset.kind, preset.description, base, preset.binary_dir
+                ));
+            }
+            out.push('\n');
+        }
+        for dir in &bs.build_dirs {
+            let mut info = vec![dir.generator.as_str()];
+            if !dir.build_type.is_empty() {
+                info.push(&dir.build_type);
+            }
+            out.push_str(&format!(
+                "- **{}** configured ({})\n",
+                dir.path,
+                info.join(", ")
+            ));
+        }
+        out.push('\n');
+    }
+
+    // Dev Container
+    if let Some(dc) = &ctx.devcontainer {
+        out.push_str("## Dev Container\n");
+        out.push_str(&format!("- **Config:** {}\n", dc.config_file));
+        if !dc.image.is_empty() {
+            out.push_str(&format!("- **Image:** `{}`\n", dc.image));
+        }
+        if !dc.dockerfile.is_empty() {
+            out.push_str(&format!("- **Dockerfile:** `{}`\n", dc.dockerfile));
+        }
+        if !dc.remote_user.is_empty() {
+            out.push_str(&format!("- **User:** {}\n", dc.remote_user));
+        }
+        out.push_str(&format!("- **Workspace:** `{}`\n", dc.workspace_folder));
+        if !dc.post_create_command.is_empty() {
+            out.push_str(&format!(
+                "- **Post-create:** `{}`\n",
+                dc.post_create_command
+            ));
+        }
+        for mount in &dc.mounts {
+            out.push_str(&format!("- **Mount:** `{}`\n", mount));
+        }
+        if dc.container.is_empty() {
+            out.push_str("- **Container:** not running\n");
+        } else {
+            out.push_str(&format!(
+                "- **Container:** {} ({})\n",
+                dc.container, dc.runtime
+            ));
+        }
+        out.push('\n');
+    }
+
+    // Compose project
+    if let Some(compose) = &ctx.compose {
+        out.push_str("## Compose Project\n");
+        out.push_str(&format!("- **Project:** {}\n", compose.name));
+        out.push_str(&format!("- **Files:** {}\n", compose.files.join(", ")));
+        for service in &compose.services {
+            out.push_str(&format!("- **Service** {}\n", format_compose_service(service)));
+            out.push_str(&format!("  - Container: `{}`\n", service.container_name));
+            if !service.build.is_empty() && !service.image.is_empty() {
+                out.push_str(&format!("  - Build context: `{}`\n", service.build));
+            }
+        }
+        out.push('\n');
     }
 
     // Containers
-    if !ctx.containers.is_empty() {
+    if ctx.containers.iter().any(|c| c.running) {
         out.push_str("## Active Containers\n");
-        for container in &ctx.containers {
+        for container in ctx.containers.iter().filter(|c| c.running) {
             out.push_str(&format!(
                 "- **{}** ({}): {}\n",
                 container.name, container.runtime, container.status
             ));
+            if !container.image.is_empty() {
+                out.push_str(&format!("  - Image: `{}`\n", container.image));
+            }
+            if !container.created.is_empty() {
+                out.push_str(&format!("  - Created: {}\n", container.created));
+            }
+            if !container.working_dir.is_empty() {
+                out.push_str(&format!("  - Working dir: `{}`\n", container.working_dir));
+            }
+            for mount in &container.mounts {
+                out.push_str(&format!(
+                    "  - Mount ({}): `{}` -> `{}`{}\n",
+                    mount.kind,
+                    mount.source,
+                    mount.destination,
+                    if mount.read_only { " (ro)" } else { "" }
+                ));
+            }
+            if !container.labels.is_empty() {
+                let labels: Vec<String> = container
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                out.push_str(&format!("  - Labels: {}\n", labels.join(", ")));
+            }
         }
         out.push('\n');
     }
 
+    let stopped: Vec<_> = ctx.containers.iter().filter(|c| !c.running).collect();
+    if !stopped.is_empty() {
+        out.push_str("## Stopped Containers\n");
+        for container in stopped {
+            out.push_str(&format!(
+                "- **{}** ({}): {}\n",
+                container.name, container.runtime, container.status
+            ));
+            if !container.finished_at.is_empty() {
+                out.push_str(&format!("  - Finished: {}\n", container.finished_at));
+            }
+            if !container.image.is_empty() {
+                out.push_str(&format!("  - Image: `{}`\n", container.image));
+            }
+        }
+        out.push('\n');
+    }
+    let warnings = format_container_warnings(ctx);
+    for warning in &warnings {
+        out.push_str(&format!("> **Warning:** {}\n", warning));
+    }
+    if !warnings.is_empty() {
+        out.push('\n');
+    }
+
     // Example commands
     if !ctx.available_commands.is_empty() {
         out.push_str("## Example Commands\n");
@@ -90,14 +375,33 @@ pub fn format_full(ctx: &Context) -> String {
         out.push_str("```\n");
     }
 
+    // Derived environment per session
+    if !ctx.environments.is_empty() {
+        out.push_str("## Active Environment\n");
+        for env in &ctx.environments {
+            let session = if env.session_id.is_empty() {
+                "unknown session"
+            } else {
+                env.session_id.as_str()
+            };
+            out.push_str(&format!(
+                "- **{}** (as of {}): {}\n",
+                session,
+                env.updated_at,
+                format_environment_summary(env)
+            ));
+        }
+        out.push('\n');
+    }
+
     // Command history
     if !ctx.command_history.is_empty() {
         out.push_str("## Recent Relevant Commands\n");
         out.push_str(
             "These commands were executed in previous sessions (useful after context compression):\n\n",
         );
-        out.push_str("| Time | Command |\n");
-        out.push_str("|------|--------|\n");
+        out.push_str("| Time | Command | Result |\n");
+        out.push_str("|------|---------|--------|\n");
         for entry in &ctx.command_history {
             let cmd_display = if entry.command.chars().count() > 80 {
                 let truncated: String = entry.command.chars().take(77).collect();
@@ -106,7 +410,18 @@ pub fn format_full(ctx: &Context) -> String {
                 entry.command.clone()
             };
             let cmd_escaped = cmd_display.replace('|', "\\|");
-            out.push_str(&format!("| {} | `{}` |\n", entry.timestamp, cmd_escaped));
+            let repeat = if entry.count > 1 {
+                format!(" ×{}", entry.count)
+            } else {
+                String::new()
+            };
+            out.push_str(&format!(
+                "| {} | `{}`{} | {} |\n",
+                entry.timestamp,
+                cmd_escaped,
+                repeat,
+                format_history_result(entry)
+            ));
         }
         out.push('\n');
     }
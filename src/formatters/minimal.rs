@@ -1,7 +1,10 @@
 //! Minimal formatter (~200 tokens) - for recovery after compression
 
 use crate::context::Context;
-use super::format_git_status;
+use super::{
+    format_active_target, format_container_warnings, format_environment_summary,
+    format_git_status,
+};
 
 /// Minimal format (~200 tokens) - for recovery after compression
 pub fn format_minimal(ctx: &Context) -> String {
@@ -9,11 +12,25 @@ pub fn format_minimal(ctx: &Context) -> String {
 
     out.push_str("# Context Recovery (Minimal)\n\n");
 
+    // The target being worked on frames everything else
+    if let Some(active) = format_active_target(ctx) {
+        out.push_str(&format!("**Active target:** {}\n\n", active));
+    }
+
     // AI hints (critical for remembering build environment)
     if !ctx.hints.is_empty() {
         out.push_str(&format!("**Hint:** {}\n\n", ctx.hints));
     }
 
+    // Builds fall back to the host when their container is gone, so say so up front
+    let warnings = format_container_warnings(ctx);
+    for warning in &warnings {
+        out.push_str(&format!("**Warning:** {}\n", warning));
+    }
+    if !warnings.is_empty() {
+        out.push('\n');
+    }
+
     // Work state is most important for recovery
     if let Some(ws) = &ctx.work_state {
         if !ws.task_summary.is_empty() {
@@ -29,6 +46,14 @@ pub fn format_minimal(ctx: &Context) -> String {
         out.push('\n');
     }
 
+    // Effective environment of the most recent session
+    if let Some(env) = ctx.environments.first() {
+        out.push_str(&format!(
+            "**Active environment:** {}\n",
+            format_environment_summary(env)
+        ));
+    }
+
     // Show only dirty repos
     let dirty_repos: Vec<_> = ctx.git_repos.iter().filter(|r| r.is_dirty).collect();
     if !dirty_repos.is_empty() {
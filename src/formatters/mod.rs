@@ -8,7 +8,10 @@ pub use minimal::format_minimal;
 pub use normal::format_normal;
 pub use full::format_full;
 
-use crate::context::{Context, GitInfo, WorkState};
+use crate::context::{
+    ActiveEnvironment, BitbakeResult, CargoWorkspace, ColconBuildResult, ComposeService,
+    ContainerInfo, Context, GitInfo, HistoryEntry, RosWorkspace, WorkState, YoctoBuild,
+};
 
 /// Main formatter dispatcher
 pub fn format_context_markdown(ctx: &Context, level: &str) -> String {
@@ -71,3 +74,378 @@ pub fn format_work_state(work_state: &WorkState) -> String {
     out.push('\n');
     out
 }
+
+/// Helper: format a derived environment as a compact one-liner
+pub fn format_environment_summary(env: &ActiveEnvironment) -> String {
+    let mut parts = Vec::new();
+
+    if !env.lunch_target.is_empty() {
+        parts.push(format!("lunch `{}`", env.lunch_target));
+    }
+    if !env.ros_distro.is_empty() {
+        parts.push(format!("ROS {}", env.ros_distro));
+    }
+    for (name, value) in &env.variables {
+        if name != "ROS_DISTRO" {
+            parts.push(format!("`{}={}`", name, value));
+        }
+    }
+    if !env.sourced.is_empty() {
+        parts.push(format!("sourced {}", env.sourced.join(", ")));
+    }
+
+    parts.join(" · ")
+}
+
+/// Helper: format a ROS workspace as a compact one-liner
+pub fn format_ros_summary(ws: &RosWorkspace) -> String {
+    let mut parts = Vec::new();
+
+    if !ws.distro.is_empty() {
+        parts.push(format!("ROS 2 {}", ws.distro));
+    }
+    if !ws.packages.is_empty() {
+        let stale = ws.packages.iter().filter(|p| p.stale).count();
+        let unbuilt = ws.packages.iter().filter(|p| !p.built).count();
+        let mut status = Vec::new();
+        if stale > 0 {
+            status.push(format!("{} stale", stale));
+        }
+        if unbuilt > 0 {
+            status.push(format!("{} not built", unbuilt));
+        }
+        if status.is_empty() {
+            parts.push(format!("{} packages", ws.packages.len()));
+        } else {
+            parts.push(format!("{} packages ({})", ws.packages.len(), status.join(", ")));
+        }
+    }
+    if let Some(build) = &ws.last_build {
+        parts.push(format!("last build: {}", format_colcon_result(build)));
+    }
+
+    parts.join(" · ")
+}
+
+/// Helper: format a colcon build outcome
+pub fn format_colcon_result(build: &ColconBuildResult) -> String {
+    if !build.failed.is_empty() {
+        format!("**FAILED** ({})", build.failed.join(", "))
+    } else if !build.incomplete.is_empty() {
+        format!("interrupted ({})", build.incomplete.join(", "))
+    } else {
+        format!("ok ({} packages)", build.succeeded.len())
+    }
+}
+
+/// Helper: format a Yocto build directory as a compact one-liner
+pub fn format_yocto_summary(build: &YoctoBuild) -> String {
+    let mut parts = Vec::new();
+
+    if !build.machine.is_empty() {
+        parts.push(format!("MACHINE `{}`", build.machine));
+    }
+    if !build.distro.is_empty() {
+        parts.push(format!("DISTRO `{}`", build.distro));
+    }
+    if !build.layers.is_empty() {
+        parts.push(format!("{} layers", build.layers.len()));
+    }
+    if let Some(result) = &build.last_build {
+        parts.push(format!("last bitbake: {}", format_bitbake_result(result)));
+    }
+
+    parts.join(" · ")
+}
+
+/// Helper: format a bitbake outcome
+pub fn format_bitbake_result(result: &BitbakeResult) -> String {
+    if !result.failed_tasks.is_empty() {
+        format!("**FAILED** ({})", result.failed_tasks.join(", "))
+    } else if !result.completed {
+        "interrupted".to_string()
+    } else {
+        format!("ok ({} tasks)", result.attempted)
+    }
+}
+
+/// Helper: format a Cargo workspace as a compact one-liner
+pub fn format_cargo_summary(ws: &CargoWorkspace) -> String {
+    let mut parts = Vec::new();
+
+    if !ws.toolchain.is_empty() {
+        parts.push(format!("toolchain `{}`", ws.toolchain));
+    }
+    parts.push(format!("{} packages", ws.packages.len()));
+
+    let binaries: Vec<&str> = ws
+        .packages
+        .iter()
+        .flat_map(|p| &p.binaries)
+        .map(|b| b.as_str())
+        .collect();
+    if !binaries.is_empty() {
+        parts.push(format!("binaries: {}", binaries.join(", ")));
+    }
+    parts.push(format!("target dir `{}`", ws.target_dir));
+
+    parts.join(" · ")
+}
+
+/// Helper: "X (container Y, lunch Z)" for the active target, if there is one
+///
+/// A target that was not selected but matches the last `lunch` is marked as inferred.
+pub fn format_active_target(ctx: &Context) -> Option<String> {
+    let selected = ctx.active_target.as_ref();
+    let (name, container, lunch) = match ctx.targets.iter().find(|t| t.active) {
+        Some(t) => (&t.name, &t.container_name, &t.lunch_target),
+        // Selected target that is no longer discovered; show what was saved
+        None => selected.map(|a| (&a.name, &a.container_name, &a.lunch_target))?,
+    };
+
+    let mut details = Vec::new();
+    if !container.is_empty() {
+        details.push(format!("container {}", container));
+    }
+    if !lunch.is_empty() {
+        details.push(format!("lunch {}", lunch));
+    }
+    if selected.is_none_or(|a| &a.name != name) {
+        details.push("inferred from lunch".to_string());
+    }
+
+    Some(if details.is_empty() {
+        name.clone()
+    } else {
+        format!("{} ({})", name, details.join(", "))
+    })
+}
+
+/// Helper: format a compose service with its state and how to get into it
+pub fn format_compose_service(service: &ComposeService) -> String {
+    let source = if service.image.is_empty() {
+        format!("build {}", service.build)
+    } else {
+        service.image.clone()
+    };
+    let mut line = format!("{} ({}) — {}", service.name, source, service.state);
+
+    // No -it: the agent's shell has no TTY, and slim images may lack bash
+    if service.state == "running" {
+        line.push_str(&format!(
+            ": `{} exec {} sh -c '<command>'`",
+            service.runtime, service.container_name
+        ));
+    }
+    if !service.profiles.is_empty() {
+        line.push_str(&format!(" [profiles: {}]", service.profiles.join(", ")));
+    }
+
+    line
+}
+
+/// Helper: state of a container referenced by a target - running, stopped or missing
+pub fn container_state(containers: &[ContainerInfo], name: &str) -> &'static str {
+    match containers.iter().find(|c| c.name == name) {
+        Some(c) if c.running => "running",
+        Some(_) => "stopped",
+        None => "missing",
+    }
+}
+
+/// Helper: warnings for containers builds expect that are not running
+///
+/// Covers `[containers] expected` plus the active target's container, or every
+/// target's container when no target is active. Runtimes that did not answer
+/// are reported too; a missing runtime only matters when containers are expected.
+pub fn format_container_warnings(ctx: &Context) -> Vec<String> {
+    let mut expected: Vec<&str> = ctx.expected_containers.iter().map(|s| s.as_str()).collect();
+    let active = ctx.targets.iter().any(|t| t.active);
+    for target in ctx.targets.iter().filter(|t| t.active || !active) {
+        if !target.container_name.is_empty() && !expected.contains(&target.container_name.as_str())
+        {
+            expected.push(&target.container_name);
+        }
+    }
+
+    let mut warnings: Vec<String> = ctx
+        .unreachable_runtimes
+        .iter()
+        .filter(|(_, reason)| !expected.is_empty() || reason != "not installed")
+        .map(|(runtime, reason)| {
+            format!(
+                "container runtime `{}` could not be queried ({})",
+                runtime, reason
+            )
+        })
+        .collect();
+
+    let containers = expected
+        .into_iter()
+        .filter_map(|name| match ctx.containers.iter().find(|c| c.name == name) {
+            Some(c) if c.running => None,
+            Some(c) => {
+                let finished = if c.finished_at.is_empty() {
+                    String::new()
+                } else {
+                    format!(", finished {}", c.finished_at)
+                };
+                Some(format!(
+                    "expected container {} is not running ({}{}) — start with `{} start {}`",
+                    name, c.status, finished, c.runtime, name
+                ))
+            }
+            // Not finding it means nothing if a runtime did not answer
+            None if !ctx.unreachable_runtimes.is_empty() => None,
+            None => Some(format!(
+                "expected container {} was not found — create it before building",
+                name
+            )),
+        });
+    warnings.extend(containers);
+    warnings
+}
+
+/// Helper: format exit status and duration of a history entry
+pub fn format_history_result(entry: &HistoryEntry) -> String {
+    let status = match entry.exit_code {
+        Some(code) if entry.failed() => format!("**FAILED (exit {})**", code),
+        Some(_) => "ok".to_string(),
+        None => String::new(),
+    };
+
+    match entry.duration_ms {
+        Some(ms) if status.is_empty() => format!("{:.1}s", ms as f64 / 1000.0),
+        Some(ms) => format!("{} {:.1}s", status, ms as f64 / 1000.0),
+        None => status,
+    }
+}
+
+/// Format `search_history` results (newest first, commands shown in full)
+pub fn format_history_search(query: &str, entries: &[HistoryEntry]) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# Command History Search: `{}`\n\n", query));
+
+    if entries.is_empty() {
+        out.push_str("No matching commands found.\n");
+        return out;
+    }
+
+    out.push_str(&format!("{} match(es), newest first:\n\n", entries.len()));
+    out.push_str("| Time | Command | Directory | Session | Result |\n");
+    out.push_str("|------|---------|-----------|---------|--------|\n");
+    for entry in entries {
+        out.push_str(&format!(
+            "| {} | `{}` | {} | {} | {} |\n",
+            entry.timestamp,
+            entry.command.replace('|', "\\|").replace('\n', " "),
+            entry.cwd,
+            entry.session_id,
+            format_history_result(entry)
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{ActiveTarget, BuildTarget};
+
+    fn target(name: &str, container: &str, active: bool) -> BuildTarget {
+        BuildTarget {
+            name: name.to_string(),
+            container_name: container.to_string(),
+            active,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_container_warnings() {
+        let mut ctx = Context {
+            targets: vec![target("emu", "aosp-build", false), target("fw", "zephyr", false)],
+            containers: vec![ContainerInfo {
+                name: "aosp-build".to_string(),
+                status: "exited (137)".to_string(),
+                runtime: "podman".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            format_container_warnings(&ctx),
+            vec![
+                "expected container aosp-build is not running (exited (137)) — \
+                 start with `podman start aosp-build`",
+                "expected container zephyr was not found — create it before building",
+            ]
+        );
+
+        // Only the active target's container counts once one is selected
+        ctx.targets[1].active = true;
+        assert_eq!(
+            format_container_warnings(&ctx),
+            vec!["expected container zephyr was not found — create it before building"]
+        );
+
+        // Without a runtime, missing containers say nothing about the container
+        ctx.containers.clear();
+        ctx.unreachable_runtimes = vec![("podman".to_string(), "not installed".to_string())];
+        assert_eq!(
+            format_container_warnings(&ctx),
+            vec!["container runtime `podman` could not be queried (not installed)"]
+        );
+
+        // A runtime that is simply not installed is not worth a warning when nothing needs it
+        ctx.targets.clear();
+        assert!(format_container_warnings(&ctx).is_empty());
+    }
+
+    #[test]
+    fn test_active_target_selected_or_inferred() {
+        let mut emu = target("emu", "aosp-build", true);
+        emu.lunch_target = "sdk_phone64_x86_64-userdebug".to_string();
+        let mut ctx = Context {
+            targets: vec![emu, target("fw", "", false)],
+            ..Default::default()
+        };
+        assert_eq!(
+            format_active_target(&ctx).unwrap(),
+            "emu (container aosp-build, lunch sdk_phone64_x86_64-userdebug, inferred from lunch)"
+        );
+
+        ctx.active_target = Some(ActiveTarget {
+            name: "emu".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            format_active_target(&ctx).unwrap(),
+            "emu (container aosp-build, lunch sdk_phone64_x86_64-userdebug)"
+        );
+
+        // A saved selection whose target is no longer discovered
+        ctx.targets.clear();
+        assert_eq!(format_active_target(&ctx).unwrap(), "emu");
+
+        ctx.active_target = None;
+        assert!(format_active_target(&ctx).is_none());
+    }
+
+    #[test]
+    fn test_compose_service_exec_without_tty() {
+        let service = ComposeService {
+            name: "ros".to_string(),
+            image: "robot/ros:humble".to_string(),
+            container_name: "robot-ros-1".to_string(),
+            runtime: "docker".to_string(),
+            state: "running".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_compose_service(&service),
+            "ros (robot/ros:humble) — running: `docker exec robot-ros-1 sh -c '<command>'`"
+        );
+    }
+}
@@ -1,7 +1,10 @@
 //! Normal formatter (~400 tokens) - balanced info
 
 use crate::context::Context;
-use super::{format_git_status, format_work_state};
+use super::{
+    container_state, format_active_target, format_cargo_summary, format_compose_service,
+    format_git_status, format_ros_summary, format_work_state, format_yocto_summary,
+};
 
 /// Normal format (~400 tokens) - balanced info
 pub fn format_normal(ctx: &Context) -> String {
@@ -9,6 +12,10 @@ pub fn format_normal(ctx: &Context) -> String {
 
     out.push_str("# Development Context\n\n");
 
+    if let Some(active) = format_active_target(ctx) {
+        out.push_str(&format!("**Active target:** {}\n\n", active));
+    }
+
     // Work state
     if let Some(ws) = &ctx.work_state {
         out.push_str(&format_work_state(ws));
@@ -37,15 +44,125 @@ pub fn format_normal(ctx: &Context) -> String {
         out.push('\n');
     }
 
-    // Active containers
-    if !ctx.containers.is_empty() {
+    // ROS workspace
+    if let Some(ros) = &ctx.ros {
+        out.push_str("## ROS Workspace\n");
+        out.push_str(&format!("{}\n\n", format_ros_summary(ros)));
+    }
+
+    // Yocto build
+    if let Some(yocto) = &ctx.yocto {
+        out.push_str("## Yocto Build\n");
+        out.push_str(&format!("{}\n\n", format_yocto_summary(yocto)));
+    }
+
+    // Cargo workspace
+    if let Some(cargo) = &ctx.cargo {
+        out.push_str("## Cargo Workspace\n");
+        out.push_str(&format!("{}\n\n", format_cargo_summary(cargo)));
+    }
+
+    // Configured build directories
+    if let Some(bs) = &ctx.build_system {
+        let dirs: Vec<String> = bs
+            .build_dirs
+            .iter()
+            .map(|d| format!("{} ({})", d.path, d.generator))
+            .collect();
+        let presets: Vec<&str> = bs
+            .cmake_presets
+            .iter()
+            .filter(|p| p.kind == "configure")
+            .map(|p| p.name.as_str())
+            .collect();
+        out.push_str("## Build System\n");
+        if !presets.is_empty() {
+            out.push_str(&format!("- **CMake presets:** {}\n", presets.join(", ")));
+        }
+        if !dirs.is_empty() {
+            out.push_str(&format!("- **Build dirs:** {}\n", dirs.join(", ")));
+        }
+        out.push('\n');
+    }
+
+    // Dev Container
+    if let Some(dc) = &ctx.devcontainer {
+        let source = if dc.image.is_empty() {
+            format!("Dockerfile {}", dc.dockerfile)
+        } else {
+            dc.image.clone()
+        };
+        let state = if dc.container.is_empty() {
+            "not running".to_string()
+        } else {
+            format!("running as {}", dc.container)
+        };
+        out.push_str("## Dev Container\n");
+        out.push_str(&format!(
+            "- {} ({}) — {}, workspace `{}`\n\n",
+            dc.config_file, source, state, dc.workspace_folder
+        ));
+    }
+
+    // Compose services
+    if let Some(compose) = &ctx.compose {
+        out.push_str(&format!("## Compose Services ({})\n", compose.name));
+        for service in &compose.services {
+            out.push_str(&format!("- {}\n", format_compose_service(service)));
+        }
+        out.push('\n');
+    }
+
+    // Active containers, with the runtime when more than one is in use
+    let multi_runtime = ctx
+        .containers
+        .iter()
+        .any(|c| c.runtime != ctx.containers[0].runtime);
+    if ctx.containers.iter().any(|c| c.running) {
         out.push_str("## Active Containers\n");
-        for container in &ctx.containers {
+        for container in ctx.containers.iter().filter(|c| c.running) {
+            let mut info = vec![container.status.as_str()];
+            if !container.image.is_empty() {
+                info.push(&container.image);
+            }
+            if multi_runtime {
+                info.push(&container.runtime);
+            }
+            out.push_str(&format!("- {} ({})\n", container.name, info.join(", ")));
+        }
+        out.push('\n');
+    }
+
+    // Stopped containers that targets or config refer to
+    if ctx.containers.iter().any(|c| !c.running) {
+        out.push_str("## Stopped Containers\n");
+        for container in ctx.containers.iter().filter(|c| !c.running) {
             out.push_str(&format!("- {} ({})\n", container.name, container.status));
         }
         out.push('\n');
     }
 
+    // Containers the build targets expect
+    let mut expected: Vec<(&str, Vec<&str>)> = Vec::new();
+    for target in ctx.targets.iter().filter(|t| !t.container_name.is_empty()) {
+        match expected.iter_mut().find(|(c, _)| *c == target.container_name) {
+            Some((_, targets)) => targets.push(&target.name),
+            None => expected.push((&target.container_name, vec![&target.name])),
+        }
+    }
+    if !expected.is_empty() {
+        out.push_str("## Target Containers\n");
+        for (container, targets) in expected {
+            out.push_str(&format!(
+                "- {} ({}): {}\n",
+                container,
+                container_state(&ctx.containers, container),
+                targets.join(", ")
+            ));
+        }
+        out.push('\n');
+    }
+
     // Connected devices
     if !ctx.adb_devices.is_empty() {
         out.push_str("## Connected Devices\n");
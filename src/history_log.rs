@@ -0,0 +1,431 @@
+//! Command history log store
+//!
+//! Owns `command-history.jsonl`: locked appends, size/age-based rotation into
+//! gzip archives, pruning of old archives, and newest-first streaming reads.
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::context::HistoryEntry;
+
+/// Chunk size used when reading the live log backwards
+const REVERSE_CHUNK_SIZE: u64 = 8192;
+
+/// Command history log with rotation settings
+#[derive(Debug, Clone)]
+pub struct HistoryLog {
+    path: PathBuf,
+    rotate_size: u64,
+    rotate_days: i64,
+    keep_archives: usize,
+    archive_days: i64,
+}
+
+/// Result of a prune run
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    pub rotated: Option<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub remaining: usize,
+}
+
+impl HistoryLog {
+    /// Build the log store from the `[history]` section
+    ///
+    /// The path comes from `log_file`, then `$CONTEXTKEEPER_LOG`, then the default.
+    pub fn from_config(config: &Config) -> Self {
+        let history_config = config.history.as_ref();
+        let path = history_config
+            .and_then(|h| h.log_file.clone())
+            .or_else(|| std::env::var("CONTEXTKEEPER_LOG").ok())
+            .map(|p| expand_home(&p))
+            .unwrap_or_else(|| {
+                PathBuf::from(home_dir()).join(".contextkeeper/command-history.jsonl")
+            });
+
+        Self {
+            path,
+            rotate_size: history_config
+                .and_then(|h| h.rotate_size_kb)
+                .unwrap_or(1024)
+                * 1024,
+            rotate_days: history_config
+                .and_then(|h| h.rotate_days)
+                .map_or(30, |d| i64::try_from(d).unwrap_or(i64::MAX)),
+            keep_archives: history_config.and_then(|h| h.keep_archives).unwrap_or(10),
+            archive_days: history_config
+                .and_then(|h| h.archive_days)
+                .map_or(180, |d| i64::try_from(d).unwrap_or(i64::MAX)),
+        }
+    }
+
+    /// Override archive retention (used by `history prune` flags)
+    pub fn with_retention(
+        mut self,
+        keep_archives: Option<usize>,
+        archive_days: Option<i64>,
+    ) -> Self {
+        if let Some(keep) = keep_archives {
+            self.keep_archives = keep;
+        }
+        if let Some(days) = archive_days {
+            self.archive_days = days;
+        }
+        self
+    }
+
+    /// Path of the live log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one entry as a JSONL line, rotating afterwards if the log is due
+    pub fn append(&self, entry: &serde_json::Value) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let _lock = self.lock()?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(format!("{}\n", entry).as_bytes())?;
+        drop(file);
+
+        if self.needs_rotation() {
+            self.rotate_locked()?;
+        }
+        Ok(())
+    }
+
+    /// Rotate if due and delete archives beyond the retention limits
+    pub fn prune(&self) -> io::Result<PruneReport> {
+        // A cutoff of now or later would delete every archive
+        let cutoff = Some(self.archive_days)
+            .filter(|days| *days >= 1)
+            .and_then(chrono::Duration::try_days)
+            .and_then(|age| Utc::now().checked_sub_signed(age))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "archive days must be at least 1 and a representable age, got {}",
+                        self.archive_days
+                    ),
+                )
+            })?;
+
+        let _lock = self.lock()?;
+        let mut report = PruneReport::default();
+
+        if self.needs_rotation() {
+            report.rotated = self.rotate_locked()?;
+        }
+
+        let mut archives = self.archives();
+        let excess = archives.len().saturating_sub(self.keep_archives);
+
+        // Archives are sorted oldest first
+        for (idx, archive) in archives.iter().enumerate() {
+            let expired = fs::metadata(archive)
+                .and_then(|m| m.modified())
+                .map(|t| DateTime::<Utc>::from(t) < cutoff)
+                .unwrap_or(false);
+            if idx < excess || expired {
+                fs::remove_file(archive)?;
+                report.removed.push(archive.clone());
+            }
+        }
+
+        archives.retain(|a| !report.removed.contains(a));
+        report.remaining = archives.len();
+        Ok(report)
+    }
+
+    /// Rotated archives, oldest first
+    pub fn archives(&self) -> Vec<PathBuf> {
+        let pattern = format!(
+            "{}/{}-*.jsonl.gz",
+            glob::Pattern::escape(&self.dir().to_string_lossy()),
+            glob::Pattern::escape(&self.stem())
+        );
+
+        let mut archives: Vec<PathBuf> = glob::glob(&pattern)
+            .map(|paths| paths.flatten().collect())
+            .unwrap_or_default();
+        archives.sort();
+        archives
+    }
+
+    /// Stream entries newest first: the live log backwards, then each archive
+    ///
+    /// Archives are only opened once the iterator reaches them.
+    pub fn iter_rev(&self) -> impl Iterator<Item = HistoryEntry> {
+        let live = fs::File::open(&self.path)
+            .ok()
+            .and_then(|file| ReverseLines::new(file).ok())
+            .into_iter()
+            .flatten();
+
+        let archives = self
+            .archives()
+            .into_iter()
+            .rev()
+            .flat_map(|archive| read_archive_lines(&archive).into_iter().rev());
+
+        live.chain(archives)
+            .filter_map(|line| parse_history_line(&line))
+    }
+
+    fn dir(&self) -> PathBuf {
+        self.path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn stem(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "command-history".to_string())
+    }
+
+    /// Take an exclusive lock shared by all writers of this log
+    fn lock(&self) -> io::Result<fs::File> {
+        fs::create_dir_all(self.dir())?;
+        let mut lock_path = self.path.clone().into_os_string();
+        lock_path.push(".lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Check size and age of the live log against the rotation thresholds
+    fn needs_rotation(&self) -> bool {
+        let size = match fs::metadata(&self.path) {
+            Ok(meta) => meta.len(),
+            Err(_) => return false,
+        };
+        if size == 0 {
+            return false;
+        }
+        if size > self.rotate_size {
+            return true;
+        }
+
+        // Age is taken from the oldest (first) entry in the live log
+        let first_line = fs::File::open(&self.path)
+            .ok()
+            .and_then(|file| io::BufRead::lines(io::BufReader::new(file)).next())
+            .and_then(Result::ok);
+        first_line
+            .and_then(|line| parse_history_line(&line))
+            .and_then(|entry| DateTime::parse_from_rfc3339(&entry.timestamp).ok())
+            .zip(chrono::Duration::try_days(self.rotate_days))
+            .map(|(ts, max_age)| Utc::now() - ts.with_timezone(&Utc) > max_age)
+            .unwrap_or(false)
+    }
+
+    /// Move the live log into a new gzip archive (caller holds the lock)
+    fn rotate_locked(&self) -> io::Result<Option<PathBuf>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        // Microsecond stamps keep archive names unique and sortable by age
+        let stamp = Utc::now().format("%Y%m%dT%H%M%S%.6fZ").to_string();
+        let mut archive = self
+            .dir()
+            .join(format!("{}-{}.jsonl.gz", self.stem(), stamp));
+        let mut suffix = 1;
+        while archive.exists() {
+            archive = self
+                .dir()
+                .join(format!("{}-{}-{}.jsonl.gz", self.stem(), stamp, suffix));
+            suffix += 1;
+        }
+
+        let content = fs::read(&self.path)?;
+        let tmp = archive.with_extension("gz.tmp");
+        let mut encoder = GzEncoder::new(fs::File::create(&tmp)?, Compression::default());
+        encoder.write_all(&content)?;
+        encoder.finish()?;
+        fs::rename(&tmp, &archive)?;
+        fs::remove_file(&self.path)?;
+
+        Ok(Some(archive))
+    }
+}
+
+/// Parse a single JSONL line written by the history logger
+pub fn parse_history_line(line: &str) -> Option<HistoryEntry> {
+    let json = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    let command = json["command"].as_str().unwrap_or("");
+    if command.is_empty() {
+        return None;
+    }
+
+    Some(HistoryEntry {
+        timestamp: json["timestamp"].as_str().unwrap_or("").to_string(),
+        command: command.to_string(),
+        cwd: json["cwd"].as_str().unwrap_or("").to_string(),
+        session_id: json["session_id"].as_str().unwrap_or("").to_string(),
+        exit_code: json["exit_code"].as_i64().map(|c| c as i32),
+        duration_ms: json["duration_ms"].as_u64(),
+        count: 1,
+    })
+}
+
+pub(crate) fn home_dir() -> String {
+    std::env::var("HOME").unwrap_or_else(|_| ".".to_string())
+}
+
+/// Expand a leading `~/` to the home directory
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(home_dir()).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Decompress an archive into its lines (oldest first)
+fn read_archive_lines(path: &Path) -> Vec<String> {
+    let mut content = String::new();
+    let decoded =
+        fs::File::open(path).and_then(|file| GzDecoder::new(file).read_to_string(&mut content));
+
+    match decoded {
+        Ok(_) => content.lines().map(str::to_string).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Iterator over the lines of a file from last to first
+struct ReverseLines<R> {
+    reader: R,
+    pos: u64,
+    carry: Vec<u8>,
+    pending: Vec<String>,
+}
+
+impl<R: Read + Seek> ReverseLines<R> {
+    fn new(mut reader: R) -> io::Result<Self> {
+        let pos = reader.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            reader,
+            pos,
+            carry: Vec::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Read the previous chunk, queueing every complete line it contains
+    fn fill(&mut self) -> io::Result<()> {
+        let len = REVERSE_CHUNK_SIZE.min(self.pos);
+        self.pos -= len;
+        self.reader.seek(SeekFrom::Start(self.pos))?;
+
+        let mut chunk = vec![0; len as usize];
+        self.reader.read_exact(&mut chunk)?;
+        chunk.append(&mut self.carry);
+
+        let mut pieces = chunk.split(|b| *b == b'\n');
+        // The first piece may continue in the previous chunk
+        let head = pieces.next().unwrap_or_default().to_vec();
+        self.pending = pieces
+            .filter(|p| !p.is_empty())
+            .map(|p| String::from_utf8_lossy(p).to_string())
+            .collect();
+
+        if self.pos == 0 {
+            if !head.is_empty() {
+                self.pending
+                    .insert(0, String::from_utf8_lossy(&head).to_string());
+            }
+        } else {
+            self.carry = head;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Iterator for ReverseLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let Some(line) = self.pending.pop() {
+                return Some(line);
+            }
+            if self.pos == 0 {
+                return None;
+            }
+            self.fill().ok()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reverse_lines_across_chunks() {
+        let lines: Vec<String> = (0..2000).map(|i| format!("line {}", i)).collect();
+        let content = format!("{}\n", lines.join("\n"));
+
+        let reversed: Vec<String> = ReverseLines::new(Cursor::new(content)).unwrap().collect();
+        let expected: Vec<String> = lines.into_iter().rev().collect();
+        assert_eq!(reversed, expected);
+    }
+
+    #[test]
+    fn test_rotate_and_read_archives() {
+        let dir = std::env::temp_dir().join(format!("ck-history-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log = HistoryLog {
+            path: dir.join("command-history.jsonl"),
+            rotate_size: 150,
+            rotate_days: 30,
+            keep_archives: 1,
+            archive_days: 180,
+        };
+
+        for i in 0..6 {
+            let entry = serde_json::json!({"timestamp": Utc::now().to_rfc3339(), "command": format!("m {}", i)});
+            log.append(&entry).unwrap();
+        }
+
+        let commands: Vec<String> = log.iter_rev().map(|e| e.command).collect();
+        assert_eq!(commands, vec!["m 5", "m 4", "m 3", "m 2", "m 1", "m 0"]);
+        assert!(!log.archives().is_empty());
+
+        let report = log.prune().unwrap();
+        assert_eq!(report.remaining, 1);
+
+        // Retention that would wipe every archive, or overflow, is refused
+        for days in [0, -5, i64::MAX] {
+            let log = HistoryLog {
+                archive_days: days,
+                ..log.clone()
+            };
+            assert!(log.prune().is_err());
+            assert_eq!(log.archives().len(), 1);
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
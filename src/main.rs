@@ -8,10 +8,14 @@ mod collectors;
 mod formatters;
 mod mcp;
 mod cli;
+mod history_log;
+mod redact;
+mod build_command;
 
 use rmcp::{transport::stdio, ServiceExt};
 
 use crate::collectors::{collect_working_files, save_work_state_to_file};
+use crate::config::read_config;
 use crate::context::WorkState;
 use crate::mcp::ContextKeeperService;
 
@@ -19,6 +23,20 @@ use crate::mcp::ContextKeeperService;
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
 
+    // History log maintenance
+    // Usage: context-keeper history <append|prune>
+    if args.get(1).map(|s| s.as_str()) == Some("history") {
+        cli::run_history_command(&args[2..])?;
+        return Ok(());
+    }
+
+    // Active target selection
+    // Usage: context-keeper target [set <name>|clear]
+    if args.get(1).map(|s| s.as_str()) == Some("target") {
+        cli::run_target_command(&args[2..])?;
+        return Ok(());
+    }
+
     // Init wizard mode
     // Usage: context-keeper init
     if args.iter().any(|arg| arg == "init") {
@@ -41,11 +59,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    // Redact mode (standalone filter; `history append` redacts by itself)
+    // Usage: echo "export TOKEN=..." | context-keeper --redact
+    if args.iter().any(|arg| arg == "--redact") {
+        cli::run_redact_command()?;
+        return Ok(());
+    }
+
     // Save state mode (for PreCompact hook)
     // Usage: context-keeper --save-state "task description"
     if let Some(pos) = args.iter().position(|arg| arg == "--save-state") {
         let task_summary = args.get(pos + 1).cloned().unwrap_or_default();
-        let files = collect_working_files();
+        let files = collect_working_files(&read_config());
 
         let state = WorkState {
             saved_at: chrono::Utc::now().to_rfc3339(),
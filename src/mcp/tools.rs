@@ -9,10 +9,14 @@ use rmcp::{
 use schemars::JsonSchema;
 use serde::Deserialize;
 
-use crate::collectors::{collect_context, collect_working_files, save_work_state_to_file};
+use crate::build_command::build_command;
+use crate::collectors::{
+    collect_context, collect_targets, collect_working_files, parse_time_bound,
+    save_active_target, save_work_state_to_file, search_command_history, HistoryQuery,
+};
 use crate::config::read_config;
 use crate::context::{TodoItem, WorkState};
-use crate::formatters::format_context_markdown;
+use crate::formatters::{format_active_target, format_context_markdown, format_history_search};
 
 /// Parameters for get_dev_context tool
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -26,7 +30,7 @@ pub struct GetDevContextParams {
 pub struct SaveWorkStateParams {
     /// Brief summary of current task (required)
     task_summary: String,
-    /// List of files currently being worked on (auto-detected from git diff if omitted)
+    /// List of files currently being worked on (auto-detected from git status if omitted)
     working_files: Option<Vec<String>>,
     /// Additional notes about current progress
     notes: Option<String>,
@@ -34,6 +38,41 @@ pub struct SaveWorkStateParams {
     todos: Option<String>,
 }
 
+/// Parameters for search_history tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchHistoryParams {
+    /// Text to search for in commands (case-insensitive substring, or a regex if `regex` is true)
+    query: String,
+    /// Treat `query` as a regular expression. Default: false
+    regex: Option<bool>,
+    /// Only commands at or after this time: RFC 3339, a date (2026-02-27), or an age like '12h' or '7d'
+    since: Option<String>,
+    /// Only commands at or before this time (same formats as `since`)
+    until: Option<String>,
+    /// Only commands run in this directory or below
+    cwd: Option<String>,
+    /// Only commands from this session id
+    session_id: Option<String>,
+    /// Maximum number of results. Default: 20
+    limit: Option<usize>,
+}
+
+/// Parameters for get_build_command tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetBuildCommandParams {
+    /// Build target name as listed in get_dev_context. Default: the active target
+    target: Option<String>,
+    /// What to do: 'build', 'flash', 'emulator' or 'shell'. Default: 'build'
+    action: Option<String>,
+}
+
+/// Parameters for set_active_target tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SetActiveTargetParams {
+    /// Build target name as listed in get_dev_context
+    target: String,
+}
+
 #[derive(Clone)]
 pub struct ContextKeeperService {
     tool_router: ToolRouter<Self>,
@@ -88,7 +127,7 @@ impl ContextKeeperService {
             .unwrap_or_default();
 
         // Auto-collect working files if not provided
-        let files = working_files.unwrap_or_else(collect_working_files);
+        let files = working_files.unwrap_or_else(|| collect_working_files(&read_config()));
 
         let state = WorkState {
             saved_at: chrono::Utc::now().to_rfc3339(),
@@ -116,6 +155,153 @@ impl ContextKeeperService {
             ))])),
         }
     }
+
+    #[tool(
+        description = "Search the full command history log (including rotated archives) for commands the agent ran earlier, e.g. the exact flash command from yesterday. Supports substring or regex matching with time range, directory and session filters."
+    )]
+    async fn search_history(
+        &self,
+        params: Parameters<SearchHistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let SearchHistoryParams {
+            query,
+            regex,
+            since,
+            until,
+            cwd,
+            session_id,
+            limit,
+        } = params.0;
+
+        let parse_bound = |value: Option<String>| match value {
+            Some(raw) => parse_time_bound(&raw).map(Some).ok_or(raw),
+            None => Ok(None),
+        };
+        let (since, until) = match (parse_bound(since), parse_bound(until)) {
+            (Ok(since), Ok(until)) => (since, until),
+            (Err(raw), _) | (_, Err(raw)) => {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Invalid time bound '{}'. Use RFC 3339, YYYY-MM-DD, or an age like 12h / 7d.",
+                    raw
+                ))]))
+            }
+        };
+
+        let history_query = HistoryQuery {
+            pattern: query.clone(),
+            regex: regex.unwrap_or(false),
+            since,
+            until,
+            cwd,
+            session_id,
+            limit: limit.unwrap_or(20),
+        };
+
+        let config = read_config();
+        match search_command_history(&config, &history_query) {
+            Ok(entries) => Ok(CallToolResult::success(vec![Content::text(
+                format_history_search(&query, &entries),
+            )])),
+            Err(e) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Invalid regex '{}': {}",
+                query, e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Get the exact command to build, flash, run the emulator for, or open a shell for a build target. The command is wrapped for the target's container and runtime and includes environment setup such as 'source build/envsetup.sh && lunch' for AOSP targets."
+    )]
+    async fn get_build_command(
+        &self,
+        params: Parameters<GetBuildCommandParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let GetBuildCommandParams { target, action } = params.0;
+        let action = action.unwrap_or_else(|| "build".to_string());
+
+        let config = read_config();
+        let context = collect_context(&config);
+        let target = match target.or_else(|| {
+            context
+                .targets
+                .iter()
+                .find(|t| t.active)
+                .map(|t| t.name.clone())
+        }) {
+            Some(target) => target,
+            None => {
+                return Ok(CallToolResult::success(vec![Content::text(
+                    "No target given and no active target set. Pass `target` or call set_active_target first.",
+                )]))
+            }
+        };
+        let text = match build_command(&config, &context, &target, &action) {
+            Ok(cmd) => {
+                let mut out = format!("```bash\n{}\n```\n", cmd.command);
+                for note in &cmd.notes {
+                    out.push_str(&format!("\n**Note:** {}\n", note));
+                }
+                out
+            }
+            Err(e) => e,
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Select the build target you are working on. The choice is stored per project and survives context compression; get_dev_context leads with it and get_build_command uses it by default."
+    )]
+    async fn set_active_target(
+        &self,
+        params: Parameters<SetActiveTargetParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = params.0.target;
+        let mut context = collect_targets(&read_config());
+
+        let target = match context.targets.iter().find(|t| t.name == name) {
+            Some(target) => target,
+            None => {
+                let names: Vec<&str> = context.targets.iter().map(|t| t.name.as_str()).collect();
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Unknown target '{}'. Available targets: {}",
+                    name,
+                    names.join(", ")
+                ))]));
+            }
+        };
+
+        let text = match save_active_target(target) {
+            Ok(active) => {
+                context.active_target = Some(active);
+                for t in &mut context.targets {
+                    t.active = t.name == name;
+                }
+                format!(
+                    "Active target: {}",
+                    format_active_target(&context).unwrap_or(name)
+                )
+            }
+            Err(e) => format!("Failed to save active target: {}", e),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Get the build target currently being worked on, with its container and lunch target."
+    )]
+    async fn get_active_target(&self) -> Result<CallToolResult, McpError> {
+        // Only an explicit selection counts here, not a target inferred from lunch
+        let context = collect_targets(&read_config());
+        let text = match context.active_target {
+            Some(_) => format!(
+                "Active target: {}",
+                format_active_target(&context).unwrap_or_default()
+            ),
+            None => "No active target. Use set_active_target to select one.".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
 }
 
 #[tool_handler]
@@ -132,7 +318,9 @@ impl ServerHandler for ContextKeeperService {
             instructions: Some(
                 "ContextKeeper provides development environment context. \
                  Call get_dev_context to retrieve build targets, containers, \
-                 and recent commands."
+                 and recent commands. Use search_history to find older commands \
+                 and get_build_command for the exact command to run for a target. \
+                 Record the target you are working on with set_active_target."
                     .into(),
             ),
         }